@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::config::{env_parse, env_var};
+use crate::report::Report;
+
+/// アラートの閾値 (USD)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    /// 前々日の合計料金
+    pub daily_total: Option<f64>,
+    /// 今月の予測
+    pub forecast: Option<f64>,
+    /// サービスごとの集計期間の料金。キーは目印を除いたサービス名 (別名を設定していれば別名)
+    pub services: HashMap<String, f64>,
+    /// 集計期間の合計料金が直近の平均から何σ離れたらアラートにするか
+    pub spike_sigma: Option<f64>,
+}
+
+impl Thresholds {
+    /// 環境変数 `ALERT_DAILY_THRESHOLD_USD`・`ALERT_FORECAST_THRESHOLD_USD`・`ALERT_SERVICE_THRESHOLDS_USD`・`ALERT_SPIKE_SIGMA` から読み込む
+    pub fn from_env() -> Result<Self, MyError> {
+        Ok(Self {
+            daily_total: env_parse("", "ALERT_DAILY_THRESHOLD_USD")?,
+            forecast: env_parse("", "ALERT_FORECAST_THRESHOLD_USD")?,
+            services: env_var("", "ALERT_SERVICE_THRESHOLDS_USD").map(|value| parse_service_thresholds(&value)).transpose()?.unwrap_or_default(),
+            spike_sigma: env_parse("", "ALERT_SPIKE_SIGMA")?,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), MyError> {
+        for (name, threshold) in [("daily_total", self.daily_total), ("forecast", self.forecast)] {
+            if let Some(threshold) = threshold.filter(|threshold| *threshold < 0.0) {
+                return Err(format!("設定エラー: thresholds.{name} は 0 以上にしてください ({threshold})").into());
+            }
+        }
+        if let Some((service, threshold)) = self.services.iter().find(|(_, threshold)| **threshold < 0.0) {
+            return Err(format!("設定エラー: thresholds.services の {service} は 0 以上にしてください ({threshold})").into());
+        }
+        if let Some(sigma) = self.spike_sigma.filter(|sigma| *sigma <= 0.0) {
+            return Err(format!("設定エラー: thresholds.spike_sigma は正の値にしてください ({sigma})").into());
+        }
+        Ok(())
+    }
+}
+
+/// `サービス名=金額` をカンマで区切って並べたサービスごとの閾値を読む (例: `AWS Lambda=1.5,Amazon Simple Storage Service=5`)
+fn parse_service_thresholds(value: &str) -> Result<HashMap<String, f64>, MyError> {
+    value.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let threshold = entry.split_once('=')
+                .and_then(|(service, threshold)| Some((service.trim().to_string(), threshold.trim().parse::<f64>().ok()?)))
+                .filter(|(service, _)| !service.is_empty());
+            threshold.ok_or_else(|| MyError::from(format!("設定エラー: ALERT_SERVICE_THRESHOLDS_USD の値が不正です: {entry} (サービス名=金額 の形式にしてください)")))
+        })
+        .collect()
+}
+
+/// スパイクの判定で比べる直近の日数
+pub const SPIKE_HISTORY_DAYS: u32 = 30;
+
+/// スパイクを判定するのに必要な最低の日数
+const SPIKE_MIN_HISTORY: usize = 7;
+
+/// 直近の日ごとの料金の平均から `value` が何σ離れているか。日数が足りない、ばらつきがない、
+/// または外れ値に引きずられないよう四分位範囲の上限 (Q3 + 1.5×IQR) を超えていなければ `None`
+pub fn spike_sigma(history: &[f64], value: f64) -> Option<f64> {
+    if history.len() < SPIKE_MIN_HISTORY {
+        return None;
+    }
+    let mean = average(history);
+    let variance = history.iter().map(|cost| (cost - mean).powi(2)).sum::<f64>() / history.len() as f64;
+    let std_dev = variance.sqrt();
+    let mut sorted = history.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let (q1, q3) = (percentile(&sorted, 0.25), percentile(&sorted, 0.75));
+    if std_dev == 0.0 || value <= q3 + 1.5 * (q3 - q1) {
+        return None;
+    }
+    Some((value - mean) / std_dev)
+}
+
+fn average(costs: &[f64]) -> f64 {
+    costs.iter().sum::<f64>() / costs.len() as f64
+}
+
+/// 昇順の値の `p` 分位点 (線形補間)
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let (lower, upper) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+    lower + (upper - lower) * rank.fract()
+}
+
+/// 直近と変わらない日の通知を省く設定
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QuietConfig {
+    /// 集計期間の合計料金がこの額 (USD) 未満なら省く対象にする
+    pub floor: f64,
+    /// 直近 7 日の平均からの差がこの割合 (%) 以内なら省く
+    #[serde(default = "default_tolerance_percent")]
+    pub tolerance_percent: f64,
+}
+
+fn default_tolerance_percent() -> f64 {
+    10.0
+}
+
+impl QuietConfig {
+    /// 平均と比べる日数
+    pub const RECENT_DAYS: u32 = 7;
+
+    /// 環境変数 `QUIET_FLOOR_USD`・`QUIET_TOLERANCE_PERCENT` から読み込む。下限が未設定なら `None`
+    pub fn from_env() -> Result<Option<Self>, MyError> {
+        if env_var("", "QUIET_FLOOR_USD").is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            floor: env_parse("", "QUIET_FLOOR_USD")?.unwrap_or_default(),
+            tolerance_percent: env_parse("", "QUIET_TOLERANCE_PERCENT")?.unwrap_or_else(default_tolerance_percent),
+        }))
+    }
+
+    /// 通知を省いてよいか。アラートがあるときや、直近の平均がわからないときは省かない
+    pub fn is_quiet(&self, report: &Report) -> bool {
+        let Some(average) = report.recent_average else {
+            return false;
+        };
+        report.alerts.is_empty()
+            && report.daily_total < self.floor
+            && (report.daily_total - average).abs() <= average * self.tolerance_percent / 100.0
+    }
+}
+
+/// 閾値を超えた項目をアラート理由として返す
+pub fn evaluate(report: &Report, thresholds: &Thresholds) -> Vec<String> {
+    let mut alerts = Vec::new();
+    if let Some(threshold) = thresholds.daily_total.filter(|threshold| report.daily_total > *threshold) {
+        alerts.push(format!("{} {} が閾値 {} を超えています", report.daily_label(), report.format_cost(report.daily_total), report.format_cost(threshold)));
+    }
+    if let Some(threshold) = thresholds.forecast.filter(|threshold| report.forecast > *threshold) {
+        alerts.push(format!("今月の予測 {} が閾値 {} を超えています", report.format_cost(report.forecast), report.format_cost(threshold)));
+    }
+    if let Some(sigma) = thresholds.spike_sigma {
+        if let Some(score) = spike_sigma(&report.daily_history, report.daily_total).filter(|score| *score >= sigma) {
+            alerts.push(format!(
+                "{} {} が直近{}日の平均 {} より {score:.1}σ 高くなっています",
+                report.daily_label(),
+                report.format_cost(report.daily_total),
+                report.daily_history.len(),
+                report.format_cost(average(&report.daily_history)),
+            ));
+        }
+    }
+    for service in &report.services {
+        if let Some(threshold) = thresholds.services.get(&service.name).filter(|threshold| service.amount > **threshold) {
+            alerts.push(format!("{} の{} {} が閾値 {} を超えています", service.name, report.daily_label(), report.format_cost(service.amount), report.format_cost(*threshold)));
+        }
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{fixtures, Annotations};
+
+    #[test]
+    fn test_is_quiet() {
+        let quiet = QuietConfig { floor: 20.0, tolerance_percent: 10.0 };
+        let report = |recent_average| Report { recent_average, ..fixtures::report() };
+        assert!(quiet.is_quiet(&report(Some(12.0))));
+        assert!(!quiet.is_quiet(&report(Some(10.0))));
+        assert!(!quiet.is_quiet(&report(None)));
+        assert!(!QuietConfig { floor: 10.0, ..quiet.clone() }.is_quiet(&report(Some(12.0))));
+        let alerted = Report { alerts: vec!["閾値超過".to_string()], ..report(Some(12.0)) };
+        assert!(!quiet.is_quiet(&alerted));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let report = fixtures::report();
+        assert!(evaluate(&report, &Thresholds::default()).is_empty());
+
+        let thresholds = Thresholds { daily_total: Some(100.0), forecast: Some(300.0), ..Thresholds::default() };
+        let alerts = evaluate(&report, &thresholds);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].starts_with("今月の予測"));
+
+        let services = HashMap::from([("AWS Lambda".to_string(), 1.0), ("Amazon Simple Storage Service".to_string(), 5.0)]);
+        let alerts = evaluate(&report, &Thresholds { services, ..Thresholds::default() });
+        assert_eq!(alerts, vec!["AWS Lambda の前々日料金 225円($1.5) が閾値 150円($1) を超えています"]);
+
+        let report = Report { daily_history: [5.0, 6.0].repeat(15), ..fixtures::report() };
+        let alerts = evaluate(&report, &Thresholds { spike_sigma: Some(3.0), ..Thresholds::default() });
+        assert_eq!(alerts, vec!["前々日料金 1875円($12.5) が直近30日の平均 825円($5.5) より 14.0σ 高くなっています"]);
+    }
+
+    #[test]
+    fn test_parse_service_thresholds() {
+        let thresholds = parse_service_thresholds("AWS Lambda=1.5, Amazon Simple Storage Service = 5,").unwrap();
+        assert_eq!(thresholds, HashMap::from([("AWS Lambda".to_string(), 1.5), ("Amazon Simple Storage Service".to_string(), 5.0)]));
+        assert!(parse_service_thresholds("AWS Lambda").is_err());
+        assert!(parse_service_thresholds("AWS Lambda=abc").is_err());
+        assert!(parse_service_thresholds("=1").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_decorated_service() {
+        let mut report = fixtures::report();
+        report.services[2].annotations = Annotations {
+            over_budget: Some(20.0),
+            rank_change: Some("↑1".to_string()),
+            usage_type_shares: vec![("Request".to_string(), 100.0)],
+        };
+        let services = HashMap::from([("AWS Lambda".to_string(), 1.0)]);
+        let alerts = evaluate(&report, &Thresholds { services, ..Thresholds::default() });
+        assert_eq!(alerts, vec!["AWS Lambda の前々日料金 225円($1.5) が閾値 150円($1) を超えています"]);
+    }
+
+    #[test]
+    fn test_spike_sigma() {
+        let history = [10.0, 11.0].repeat(15);
+        assert_eq!(spike_sigma(&history, 13.0), Some(5.0));
+        // σ では 3.2 離れているが、四分位範囲の上限 (12.5) を超えていない
+        assert_eq!(spike_sigma(&history, 12.1), None);
+        assert_eq!(spike_sigma(&[10.0; 30], 20.0), None);
+        assert_eq!(spike_sigma(&history[..6], 13.0), None);
+    }
+}
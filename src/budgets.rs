@@ -0,0 +1,122 @@
+use aws_sdk_budgets as budgets;
+use aws_sdk_budgets::types::{Budget, BudgetType, Notification, NotificationType, ThresholdType};
+use futures::future::try_join_all;
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::config::{env_parse, env_var};
+use crate::report::{Breakdown, ServiceCost};
+
+/// 予算の見出しに付ける目印
+const WARNING: &str = "⚠";
+
+/// AWS Budgets の予算の消化状況を載せる設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct BudgetsConfig {
+    /// 予算を作成したアカウント
+    pub account_id: String,
+    /// 実績のアラートの閾値が設定されていない予算で、警告する消化率 (%)
+    #[serde(default = "default_warning_percent")]
+    pub warning_percent: f64,
+}
+
+fn default_warning_percent() -> f64 {
+    80.0
+}
+
+impl BudgetsConfig {
+    /// 環境変数 `BUDGETS_ACCOUNT_ID`・`BUDGETS_WARNING_PERCENT` から読み込む。アカウントが未設定なら `None`
+    pub fn from_env() -> Result<Option<Self>, MyError> {
+        let Some(account_id) = env_var("", "BUDGETS_ACCOUNT_ID") else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            account_id,
+            warning_percent: env_parse("", "BUDGETS_WARNING_PERCENT")?.unwrap_or_else(default_warning_percent),
+        }))
+    }
+}
+
+/// 1 つの予算の消化状況 (USD)
+#[derive(Debug, Clone, PartialEq)]
+struct BudgetStatus {
+    name: String,
+    actual: f64,
+    limit: f64,
+    /// 警告する消化率 (%)
+    threshold_percent: f64,
+}
+
+impl BudgetStatus {
+    fn percent(&self) -> f64 {
+        if self.limit > 0.0 { self.actual / self.limit * 100.0 } else { 0.0 }
+    }
+
+    fn is_warning(&self) -> bool {
+        self.percent() >= self.threshold_percent
+    }
+
+    /// 実績を料金にした項目。予算名に消化率と上限を添える
+    fn to_service_cost(&self) -> ServiceCost {
+        let marker = if self.is_warning() { format!(" {WARNING}") } else { String::new() };
+        ServiceCost::new(format!("{} ({:.1}% / 上限 ${:.2}){marker}", self.name, self.percent(), self.limit), self.actual)
+    }
+}
+
+/// 料金の予算の実績と上限を内訳として返す。閾値を超えた予算があれば見出しに目印を付ける
+pub async fn fetch_breakdown(config: &BudgetsConfig) -> Result<Breakdown, MyError> {
+    let sdk_config = aws_config::load_from_env().await;
+    let client = budgets::Client::new(&sdk_config);
+    let pages: Vec<_> = client.describe_budgets().account_id(&config.account_id).into_paginator().send().try_collect().await?;
+    let cost_budgets: Vec<Budget> = pages.into_iter()
+        .flat_map(|page| page.budgets.unwrap_or_default())
+        .filter(|budget| budget.budget_type == BudgetType::Cost)
+        .collect();
+    let statuses = try_join_all(cost_budgets.iter().map(|budget| fetch_status(&client, config, budget))).await?;
+
+    let title = if statuses.iter().any(BudgetStatus::is_warning) {
+        format!("AWS Budgets の消化状況 {WARNING}")
+    } else {
+        "AWS Budgets の消化状況".to_string()
+    };
+    Ok(Breakdown { title, items: statuses.iter().map(BudgetStatus::to_service_cost).collect() })
+}
+
+async fn fetch_status(client: &budgets::Client, config: &BudgetsConfig, budget: &Budget) -> Result<BudgetStatus, MyError> {
+    let notifications = client.describe_notifications_for_budget()
+        .account_id(&config.account_id)
+        .budget_name(&budget.budget_name)
+        .send()
+        .await?
+        .notifications
+        .unwrap_or_default();
+    let amount = |spend: Option<&budgets::types::Spend>| spend.and_then(|spend| spend.amount.parse::<f64>().ok()).unwrap_or(0.0);
+    Ok(BudgetStatus {
+        name: budget.budget_name.clone(),
+        actual: amount(budget.calculated_spend.as_ref().map(|spend| &spend.actual_spend)),
+        limit: amount(budget.budget_limit.as_ref()),
+        threshold_percent: threshold_percent(&notifications).unwrap_or(config.warning_percent),
+    })
+}
+
+/// 実績に対する割合のアラートのうち、最も低い閾値
+fn threshold_percent(notifications: &[Notification]) -> Option<f64> {
+    notifications.iter()
+        .filter(|notification| notification.notification_type == NotificationType::Actual)
+        .filter(|notification| notification.threshold_type.as_ref().is_none_or(|threshold_type| *threshold_type == ThresholdType::Percentage))
+        .map(|notification| notification.threshold)
+        .min_by(f64::total_cmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_service_cost() {
+        let status = BudgetStatus { name: "monthly".to_string(), actual: 255.0, limit: 300.0, threshold_percent: 80.0 };
+        assert_eq!(status.to_service_cost().name, "monthly (85.0% / 上限 $300.00) ⚠");
+        let status = BudgetStatus { actual: 150.0, ..status };
+        assert_eq!(status.to_service_cost().name, "monthly (50.0% / 上限 $300.00)");
+    }
+}
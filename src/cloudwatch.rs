@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use aws_sdk_cloudwatch as cloudwatch;
+use aws_sdk_cloudwatch::primitives::DateTime;
+use aws_sdk_cloudwatch::types::{Dimension, MetricDataQuery, Statistic};
+use chrono::{Datelike, NaiveDate};
+
+use crate::MyError;
+use crate::report::{Breakdown, ServiceCost};
+
+/// 請求のメトリクスは us-east-1 にだけ発行される
+const BILLING_REGION: &str = "us-east-1";
+
+/// 1 日ごとの最大値を取る
+const DAY_SECONDS: i32 = 86_400;
+
+/// CloudWatch Logs の取り込みの 1 GB あたりの料金 (USD、us-east-1 の単価)
+const LOG_INGESTION_PRICE_PER_GB: f64 = 0.50;
+
+/// CloudWatch の請求見込み額 (`AWS/Billing` の `EstimatedCharges`) から求めた料金 (USD)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimatedCharges {
+    /// 期間 [start, end) の料金
+    pub period_total: f64,
+    /// 今月の現時点料金
+    pub monthly_cost: f64,
+}
+
+/// 請求見込み額を取得する。見込み額は月初からの累計なので、期間の料金は前日までとの差にする
+pub async fn fetch_estimated_charges(today: NaiveDate, start: NaiveDate, end: NaiveDate) -> Result<EstimatedCharges, MyError> {
+    let sdk_config = aws_config::from_env().region(aws_config::Region::new(BILLING_REGION)).load().await;
+    let client = cloudwatch::Client::new(&sdk_config);
+    let from = start.min(today.with_day(1).ok_or("Failed to calculate the first day of this month")?) - chrono::Duration::days(1);
+    let to = today + chrono::Duration::days(1);
+
+    let output = client.get_metric_statistics()
+        .namespace("AWS/Billing")
+        .metric_name("EstimatedCharges")
+        .dimensions(Dimension::builder().name("Currency").value("USD").build())
+        .start_time(DateTime::from_secs(midnight_secs(from)))
+        .end_time(DateTime::from_secs(midnight_secs(to)))
+        .period(DAY_SECONDS)
+        .statistics(Statistic::Maximum)
+        .send()
+        .await?;
+    let daily: BTreeMap<NaiveDate, f64> = output.datapoints.unwrap_or_default().into_iter()
+        .filter_map(|datapoint| {
+            let date = chrono::DateTime::from_timestamp(datapoint.timestamp?.secs(), 0)?.date_naive();
+            Some((date, datapoint.maximum?))
+        })
+        .collect();
+    let monthly_cost = *daily.values().last().ok_or("CloudWatch に EstimatedCharges のデータがありません")?;
+    Ok(EstimatedCharges { period_total: period_total(&daily, start, end), monthly_cost })
+}
+
+/// 期間 [start, end) の料金。前日の累計が前月のものなら、その日からの累計をそのまま使う
+fn period_total(daily: &BTreeMap<NaiveDate, f64>, start: NaiveDate, end: NaiveDate) -> f64 {
+    let last = end - chrono::Duration::days(1);
+    let before = start - chrono::Duration::days(1);
+    let total_at = |date: NaiveDate| daily.range(..=date).next_back().map(|(_, total)| *total).unwrap_or(0.0);
+    let until_before = if before.month() == last.month() { total_at(before) } else { 0.0 };
+    (total_at(last) - until_before).max(0.0)
+}
+
+/// 期間 [start, end) に取り込んだバイト数の多いロググループ `count` 件を、取り込みの料金を見積もった内訳として返す。
+/// `period` は期間の名前。取り込みがなければ `None`
+pub async fn fetch_log_group_ingestion(start: NaiveDate, end: NaiveDate, period: &str, count: usize) -> Result<Option<Breakdown>, MyError> {
+    let sdk_config = aws_config::load_from_env().await;
+    let client = cloudwatch::Client::new(&sdk_config);
+    let (from, to) = (midnight_secs(start), midnight_secs(end));
+    // ロググループごとの IncomingBytes を期間全体で 1 つの値に合計する
+    let query = MetricDataQuery::builder()
+        .id("incoming")
+        .expression(format!(r#"SEARCH('{{AWS/Logs,LogGroupName}} MetricName="IncomingBytes"', 'Sum', {})"#, to - from))
+        .build();
+    let mut log_groups = Vec::new();
+    let mut next_token = None;
+    loop {
+        let output = client.get_metric_data()
+            .metric_data_queries(query.clone())
+            .start_time(DateTime::from_secs(from))
+            .end_time(DateTime::from_secs(to))
+            .set_next_token(next_token)
+            .send()
+            .await?;
+        for result in output.metric_data_results.unwrap_or_default() {
+            let bytes: f64 = result.values.unwrap_or_default().iter().sum();
+            log_groups.extend(result.label.map(|label| (label, bytes)));
+        }
+        next_token = output.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(log_ingestion_breakdown(period, log_groups, count))
+}
+
+/// ロググループごとの取り込みバイト数を多い順に `count` 件並べ、取り込みの料金を見積もった内訳にする
+fn log_ingestion_breakdown(period: &str, mut log_groups: Vec<(String, f64)>, count: usize) -> Option<Breakdown> {
+    log_groups.retain(|(_, bytes)| *bytes > 0.0);
+    if log_groups.is_empty() {
+        return None;
+    }
+    log_groups.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let items = log_groups.into_iter()
+        .take(count)
+        .map(|(name, bytes)| {
+            let gigabytes = bytes / 1e9;
+            ServiceCost::new(format!("{name} ({gigabytes:.1} GB)"), gigabytes * LOG_INGESTION_PRICE_PER_GB)
+        })
+        .collect();
+    Some(Breakdown { title: format!("{period}の取り込み量の多いロググループ (us-east-1 の単価で概算)"), items })
+}
+
+fn midnight_secs(date: NaiveDate) -> i64 {
+    date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_total() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+        let daily = BTreeMap::from([(date(9, 30), 300.0), (date(10, 1), 10.0), (date(10, 2), 25.0), (date(10, 3), 32.0)]);
+        assert_eq!(period_total(&daily, date(10, 3), date(10, 4)), 7.0);
+        assert_eq!(period_total(&daily, date(10, 1), date(10, 2)), 10.0);
+        assert_eq!(period_total(&daily, date(10, 2), date(10, 4)), 22.0);
+    }
+
+    #[test]
+    fn test_log_ingestion_breakdown() {
+        let log_groups = vec![("/aws/lambda/api".to_string(), 4e9), ("/aws/lambda/idle".to_string(), 0.0), ("/ecs/web".to_string(), 12e9)];
+        let breakdown = log_ingestion_breakdown("2026-10-14", log_groups.clone(), 5).unwrap();
+        assert_eq!(breakdown.items.len(), 2);
+        assert_eq!(breakdown.items[0].name, "/ecs/web (12.0 GB)");
+        assert_eq!(breakdown.items[0].amount, 6.0);
+        assert_eq!(log_ingestion_breakdown("2026-10-14", log_groups, 1).unwrap().items.len(), 1);
+        assert_eq!(log_ingestion_breakdown("2026-10-14", Vec::new(), 5), None);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use aws_sdk_appconfigdata as appconfigdata;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::MyError;
+use crate::config::{env_required, env_var, Config};
+use crate::notifier::slack::SlackFormat;
+
+/// AppConfig のセッション。ウォームスタートの呼び出しでは前回のトークンでポーリングする
+static SESSION: OnceLock<Mutex<Option<Session>>> = OnceLock::new();
+
+struct Session {
+    token: String,
+    flags: FeatureFlags,
+}
+
+/// フィーチャーフラグを取得する AWS AppConfig の構成プロファイル
+#[derive(Clone, Deserialize)]
+pub struct AppConfigSource {
+    pub application: String,
+    pub environment: String,
+    pub profile: String,
+}
+
+impl AppConfigSource {
+    /// 環境変数 `APPCONFIG_APPLICATION`・`APPCONFIG_ENVIRONMENT`・`APPCONFIG_PROFILE` から読み込む。
+    /// アプリケーションが未設定なら `None`
+    pub fn from_env() -> Result<Option<Self>, MyError> {
+        let Some(application) = env_var("", "APPCONFIG_APPLICATION") else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            application,
+            environment: env_required("", "APPCONFIG_ENVIRONMENT", "APPCONFIG_APPLICATION")?,
+            profile: env_required("", "APPCONFIG_PROFILE", "APPCONFIG_APPLICATION")?,
+        }))
+    }
+}
+
+/// AppConfig で切り替えるフラグ。AppConfig に定義されていないフラグは設定の値のまま
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureFlags {
+    /// 今月の予測を載せるか
+    pub include_forecast: Option<bool>,
+    /// Slack を Block Kit のレイアウトで送るか
+    pub new_message_layout: Option<bool>,
+}
+
+impl FeatureFlags {
+    /// フィーチャーフラグ形式の構成 (`{"include_forecast": {"enabled": true}, ...}`) を読む
+    fn parse(content: &[u8]) -> Result<Self, MyError> {
+        #[derive(Deserialize)]
+        struct Flag {
+            enabled: bool,
+        }
+        let flags: HashMap<String, Flag> = serde_json::from_slice(content)?;
+        let enabled = |name: &str| flags.get(name).map(|flag| flag.enabled);
+        Ok(Self { include_forecast: enabled("include_forecast"), new_message_layout: enabled("new_message_layout") })
+    }
+
+    fn apply(&self, config: &mut Config) {
+        if let Some(include_forecast) = self.include_forecast {
+            config.sections.forecast = include_forecast;
+        }
+        if self.new_message_layout == Some(true) {
+            for slack in [&mut config.channels.slack, &mut config.alert_channels.slack].into_iter().flatten() {
+                slack.format = SlackFormat::Blocks;
+            }
+        }
+    }
+}
+
+/// AppConfig をポーリングしてフィーチャーフラグを反映した設定を返す。
+/// 取得に失敗したときはレポートを止めないよう、設定の値のまま続ける
+pub async fn apply(config: &Config) -> Config {
+    let mut config = config.clone();
+    let Some(source) = config.feature_flags.clone() else {
+        return config;
+    };
+    match poll(&source).await {
+        Ok(flags) => flags.apply(&mut config),
+        Err(e) => eprintln!("フィーチャーフラグを取得できなかったため設定の値を使います: {e}"),
+    }
+    config
+}
+
+async fn poll(source: &AppConfigSource) -> Result<FeatureFlags, MyError> {
+    let sdk_config = aws_config::load_from_env().await;
+    let client = appconfigdata::Client::new(&sdk_config);
+    let mut session = SESSION.get_or_init(Default::default).lock().await;
+
+    let token = match session.as_ref() {
+        Some(session) => session.token.clone(),
+        None => client.start_configuration_session()
+            .application_identifier(&source.application)
+            .environment_identifier(&source.environment)
+            .configuration_profile_identifier(&source.profile)
+            .send()
+            .await?
+            .initial_configuration_token
+            .ok_or("AppConfig のセッションを開始できませんでした")?,
+    };
+    let output = match client.get_latest_configuration().configuration_token(token).send().await {
+        Ok(output) => output,
+        Err(e) => {
+            // トークンの期限切れに備えて次回はセッションを作り直す
+            *session = None;
+            return Err(e.into());
+        }
+    };
+
+    // 前回から変わっていなければ構成は空で返る
+    let flags = match output.configuration.as_ref().map(|blob| blob.as_ref()).filter(|content| !content.is_empty()) {
+        Some(content) => FeatureFlags::parse(content)?,
+        None => session.as_ref().map(|session| session.flags.clone()).unwrap_or_default(),
+    };
+    let token = output.next_poll_configuration_token.ok_or("AppConfig の次のトークンがありません")?;
+    *session = Some(Session { token, flags: flags.clone() });
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let flags = FeatureFlags::parse(br#"{"include_forecast": {"enabled": false}, "unknown": {"enabled": true}}"#).unwrap();
+        assert_eq!(flags, FeatureFlags { include_forecast: Some(false), new_message_layout: None });
+
+        let mut config = Config::default();
+        flags.apply(&mut config);
+        assert!(!config.sections.forecast);
+    }
+}
@@ -0,0 +1,73 @@
+use aws_sdk_s3 as s3;
+
+use crate::MyError;
+use crate::config::Config;
+
+/// 設定ファイルを読み込む。`s3://bucket/key` なら S3 から取得する。
+/// 拡張子が `.yaml` / `.yml` なら YAML、それ以外は TOML として読む
+pub async fn load(location: &str) -> Result<Config, MyError> {
+    let content = match location.strip_prefix("s3://") {
+        Some(path) => fetch_s3(path).await?,
+        None => std::fs::read_to_string(location).map_err(|e| format!("設定エラー: {location} を読み込めません: {e}"))?,
+    };
+    parse(location, &content)
+}
+
+fn parse(location: &str, content: &str) -> Result<Config, MyError> {
+    let parsed = if location.ends_with(".yaml") || location.ends_with(".yml") {
+        serde_yaml::from_str(content).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(content).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|e| format!("設定エラー: {location} の内容が不正です: {e}").into())
+}
+
+async fn fetch_s3(path: &str) -> Result<String, MyError> {
+    let (bucket, key) = path.split_once('/').ok_or_else(|| format!("設定エラー: S3 のパスが不正です: s3://{path}"))?;
+    let sdk_config = aws_config::load_from_env().await;
+    let output = s3::Client::new(&sdk_config).get_object().bucket(bucket).key(key).send().await?;
+    let bytes = output.body.collect().await?.into_bytes();
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toml() {
+        let config = parse("config.toml", r#"
+display_count = 10
+currency = "USD"
+timezone = "Asia/Tokyo"
+
+[thresholds]
+forecast = 500.0
+
+[channels.slack]
+webhook_url = { secret_id = "billing/slack-webhook" }
+format = "blocks"
+
+[service_aliases]
+"Amazon Elastic Compute Cloud - Compute" = "EC2"
+"#).unwrap();
+        assert_eq!(config.display_count, 10);
+        assert_eq!(config.timezone, chrono_tz::Asia::Tokyo);
+        assert_eq!(config.thresholds.forecast, Some(500.0));
+        assert!(config.channels.slack.is_some());
+        assert_eq!(config.service_aliases["Amazon Elastic Compute Cloud - Compute"], "EC2");
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let config = parse("config.yaml", "
+monthly_budget: 300
+alert_channels:
+  discord:
+    webhook_url: https://discord.com/api/webhooks/1/x
+").unwrap();
+        assert_eq!(config.monthly_budget, Some(300.0));
+        assert!(config.alert_channels.discord.is_some());
+        assert!(parse("config.yaml", "display_count: many").is_err());
+    }
+}
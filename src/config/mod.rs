@@ -0,0 +1,571 @@
+pub mod feature_flags;
+mod file;
+mod ssm;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::alert::{QuietConfig, Thresholds};
+use crate::budgets::BudgetsConfig;
+use crate::config::feature_flags::AppConfigSource;
+use crate::cost_explorer::hourly::HourlyConfig;
+use crate::cost_explorer::window::{Window, WindowGranularity};
+use crate::cost_explorer::{CostExplorerConfig, GroupBy};
+use crate::cur::CurConfig;
+use crate::notifier::ChannelsConfig;
+use crate::notifier::opsgenie::OpsgenieConfig;
+use crate::notifier::pagerduty::PagerDutyConfig;
+use crate::provider::ProviderKind;
+use crate::report::{ReportType, Sections};
+
+/// アラート用の通知先を設定する環境変数の接頭辞 (例: `ALERTS_SLACK_WEBHOOK_URL`)
+pub const ALERTS_PREFIX: &str = "ALERTS_";
+
+/// ランキングに表示できるサービス数の上限。Discord の埋め込みのフィールド数の上限 (25) に、合計のフィールドと `その他` の行を合わせて収まるようにする
+pub const MAX_DISPLAY_COUNT: usize = 20;
+
+/// 月次締めレポートのランキングに表示するサービス数
+const MONTHLY_DISPLAY_COUNT: usize = 10;
+
+/// 月次締めレポートで前月から増えたサービスを載せる数
+const MONTHLY_MOVERS_COUNT: usize = 5;
+
+/// Lambda のパッケージに同梱する設定ファイル
+const BUNDLED_CONFIG_FILE: &str = "config.toml";
+
+/// コールドスタート時に一度だけ読み込んで検証する設定
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// レポートの種類
+    pub report_type: ReportType,
+    /// ランキングに表示するサービス数
+    pub display_count: usize,
+    /// ランキングの上位 3 件の順位をメダル (🥇🥈🥉) で表す
+    pub ranking_medals: bool,
+    /// この額 (USD) 未満のサービスはランキングに出さず、`その他` にまとめる
+    pub ranking_floor: f64,
+    /// 表示通貨 (ISO 4217)
+    pub currency: String,
+    /// 「前々日」や月の境界を決めるタイムゾーン (例: `Asia/Tokyo`)
+    pub timezone: Tz,
+    /// 月の予算 (USD)
+    pub monthly_budget: Option<f64>,
+    /// サービスごとの月の予算 (USD)。キーはランキングの表示名 (別名を設定していれば別名)。
+    /// 今月の現時点料金が超えたサービスはランキングで目立たせる
+    pub service_budgets: HashMap<String, f64>,
+    /// 消化状況を載せる AWS Budgets の予算
+    pub budgets: Option<BudgetsConfig>,
+    /// 未接続の EBS ボリューム・関連付けのない Elastic IP・使われていない NAT ゲートウェイを探して週次レポートに載せる
+    pub idle_resources: bool,
+    /// 集計期間に取り込んだバイト数の多いロググループを載せる件数。未設定なら載せない
+    pub log_group_count: Option<usize>,
+    pub thresholds: Thresholds,
+    /// 直近と変わらない日の通知を省く。未設定なら毎日通知する
+    pub quiet: Option<QuietConfig>,
+    /// 通常の日次レポートの通知先
+    pub channels: ChannelsConfig,
+    /// アラートの通知先。未設定なら `channels` に送る
+    pub alert_channels: ChannelsConfig,
+    pub pagerduty: Option<PagerDutyConfig>,
+    pub opsgenie: Option<OpsgenieConfig>,
+    /// Slack アプリ (スラッシュコマンド・ボタン操作) の署名シークレット
+    pub slack_signing_secret: Option<String>,
+    /// Cost Explorer のサービス名に対する表示名 (例: `Amazon Elastic Compute Cloud - Compute` → `EC2`)
+    pub service_aliases: HashMap<String, String>,
+    /// 料金の取得元
+    pub provider: ProviderKind,
+    /// Cost Explorer の問い合わせ方 (ランキングの集計単位など)
+    pub cost_explorer: CostExplorerConfig,
+    /// Athena で問い合わせる CUR。Cost Explorer では取れないリソースやタグの単位の内訳に使う
+    pub cur: Option<CurConfig>,
+    pub sections: Sections,
+    /// 呼び出しごとにポーリングするフィーチャーフラグの取得先
+    pub feature_flags: Option<AppConfigSource>,
+    /// 実行環境 (dev・stg・prod など)。同じ名前のプロファイルを適用する
+    pub environment: Option<String>,
+    /// 件名の接頭辞。未設定なら `[{environment}]`
+    pub message_prefix: Option<String>,
+    /// 環境ごとのプロファイル
+    pub profiles: HashMap<String, Profile>,
+    /// `false` なら集計とログ出力だけ行い、通知しない (障害対応中の凍結やスケジュール変更の確認用)
+    pub notifications_enabled: bool,
+    /// `true` なら各通知先に送る内容を組み立ててログに出し、送信はしない
+    pub dry_run: bool,
+}
+
+/// 環境ごとに切り替える設定
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub message_prefix: Option<String>,
+    /// 設定されているチャネルを置き換える
+    pub channels: ChannelsConfig,
+    pub alert_channels: ChannelsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            report_type: ReportType::default(),
+            display_count: 5,
+            ranking_medals: false,
+            ranking_floor: 0.0,
+            currency: "JPY".to_string(),
+            timezone: Tz::UTC,
+            monthly_budget: None,
+            service_budgets: HashMap::new(),
+            budgets: None,
+            idle_resources: false,
+            log_group_count: None,
+            thresholds: Thresholds::default(),
+            quiet: None,
+            channels: ChannelsConfig::default(),
+            alert_channels: ChannelsConfig::default(),
+            pagerduty: None,
+            opsgenie: None,
+            slack_signing_secret: None,
+            service_aliases: HashMap::new(),
+            provider: ProviderKind::default(),
+            cost_explorer: CostExplorerConfig::default(),
+            cur: None,
+            sections: Sections::default(),
+            feature_flags: None,
+            environment: None,
+            message_prefix: None,
+            profiles: HashMap::new(),
+            notifications_enabled: true,
+            dry_run: false,
+        }
+    }
+}
+
+impl Config {
+    /// 設定を読み込み、設定されている環境変数で上書きして検証する。読み込み元の優先順位は
+    /// `CONFIG_SSM_PATH` の SSM Parameter Store、`CONFIG_FILE` のファイル (`s3://` なら S3)、同梱の `config.toml`
+    pub async fn load() -> Result<Self, MyError> {
+        let base = if let Some(path) = env_var("", "CONFIG_SSM_PATH") {
+            println!("設定を SSM Parameter Store ({path}) から読み込みます");
+            ssm::load(&path).await?
+        } else if let Some(location) = env_var("", "CONFIG_FILE") {
+            println!("設定を {location} から読み込みます");
+            file::load(&location).await?
+        } else if Path::new(BUNDLED_CONFIG_FILE).exists() {
+            file::load(BUNDLED_CONFIG_FILE).await?
+        } else {
+            Self::default()
+        };
+        let config = base.with_profile()?.with_env_overrides()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 環境変数から読み込んで検証する
+    pub fn from_env() -> Result<Self, MyError> {
+        let config = Self::default().with_profile()?.with_env_overrides()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 環境変数 `ENVIRONMENT` (なければ設定の `environment`) と同じ名前のプロファイルを適用する
+    fn with_profile(mut self) -> Result<Self, MyError> {
+        self.environment = env_var("", "ENVIRONMENT").or(self.environment);
+        let Some(environment) = &self.environment else {
+            return Ok(self);
+        };
+        match self.profiles.get(environment).cloned() {
+            Some(profile) => {
+                self.channels.merge(profile.channels);
+                self.alert_channels.merge(profile.alert_channels);
+                self.message_prefix = profile.message_prefix.or(self.message_prefix);
+            }
+            None if !self.profiles.is_empty() => {
+                return Err(format!("設定エラー: 環境 {environment} のプロファイルがありません").into());
+            }
+            None => {}
+        }
+        Ok(self)
+    }
+
+    /// 設定したタイムゾーンでの今日の日付
+    pub fn today(&self) -> NaiveDate {
+        chrono::Utc::now().with_timezone(&self.timezone).date_naive()
+    }
+
+    /// レポートの種類に合わせて集計期間と載せる内容を決める。集計期間を明示していれば変えない
+    pub fn with_report_type(mut self) -> Self {
+        if self.report_type == ReportType::Daily {
+            self.cost_explorer.purchase_recommendations = false;
+        }
+        if self.report_type != ReportType::Weekly {
+            self.idle_resources = false;
+        }
+        if self.cost_explorer.window.is_some() {
+            return self;
+        }
+        match self.report_type {
+            ReportType::Daily => {}
+            ReportType::Weekly => {
+                // 日次レポートと同じく day_offset 日前を最終日にした 7 日にする
+                let end = self.today() - chrono::Duration::days(i64::from(self.cost_explorer.day_offset) - 1);
+                self.cost_explorer.window = Some(Window { start: end - chrono::Duration::days(7), end, granularity: WindowGranularity::Daily });
+                self.cost_explorer.compare_previous = true;
+                self.cost_explorer.daily_chart = true;
+            }
+            ReportType::Monthly => {
+                // 月の始まり (1〜3 日後まで) に実行し、確定した先月を集計する
+                self.cost_explorer.window = self.cost_explorer.last_month(self.today()).ok();
+                self.cost_explorer.movers_count = self.cost_explorer.movers_count.or(Some(MONTHLY_MOVERS_COUNT));
+                self.display_count = MONTHLY_DISPLAY_COUNT;
+                self.sections.forecast = false;
+            }
+        }
+        self
+    }
+
+    /// 件名の接頭辞。明示されていなければ環境名から作る
+    pub fn message_prefix(&self) -> Option<String> {
+        self.message_prefix.clone().or_else(|| self.environment.as_ref().map(|environment| format!("[{environment}]")))
+    }
+
+    /// 設定されている環境変数の値で上書きする。通知先はチャネル単位で置き換える
+    fn with_env_overrides(mut self) -> Result<Self, MyError> {
+        if let Some(report_type) = env_parse("", "REPORT_TYPE")? {
+            self.report_type = report_type;
+        }
+        if let Some(display_count) = env_parse("", "DISPLAY_COUNT")? {
+            self.display_count = display_count;
+        }
+        if let Some(ranking_floor) = env_parse("", "RANKING_FLOOR_USD")? {
+            self.ranking_floor = ranking_floor;
+        }
+        if let Some(currency) = env_var("", "CURRENCY") {
+            self.currency = currency.to_ascii_uppercase();
+        }
+        if let Some(timezone) = env_parse("", "REPORT_TIMEZONE")? {
+            self.timezone = timezone;
+        }
+        self.monthly_budget = env_parse("", "MONTHLY_BUDGET_USD")?.or(self.monthly_budget);
+        self.budgets = BudgetsConfig::from_env()?.or(self.budgets);
+        self.log_group_count = env_parse("", "LOG_GROUP_COUNT")?.or(self.log_group_count);
+        let thresholds = Thresholds::from_env()?;
+        self.thresholds.daily_total = thresholds.daily_total.or(self.thresholds.daily_total);
+        self.thresholds.forecast = thresholds.forecast.or(self.thresholds.forecast);
+        self.thresholds.services.extend(thresholds.services);
+        self.thresholds.spike_sigma = thresholds.spike_sigma.or(self.thresholds.spike_sigma);
+        self.quiet = QuietConfig::from_env()?.or(self.quiet);
+        self.channels.merge(ChannelsConfig::from_env("")?);
+        self.alert_channels.merge(ChannelsConfig::from_env(ALERTS_PREFIX)?);
+        self.pagerduty = PagerDutyConfig::from_env()?.or(self.pagerduty);
+        self.opsgenie = OpsgenieConfig::from_env()?.or(self.opsgenie);
+        self.slack_signing_secret = env_var("", "SLACK_SIGNING_SECRET").or(self.slack_signing_secret);
+        if let Some(provider) = env_parse("", "COST_PROVIDER")? {
+            self.provider = provider;
+        }
+        match (env_var("", "GROUP_BY_TAG"), env_var("", "GROUP_BY_COST_CATEGORY")) {
+            (Some(_), Some(_)) => return Err("設定エラー: GROUP_BY_TAG と GROUP_BY_COST_CATEGORY は同時に設定できません".into()),
+            (Some(key), None) => self.cost_explorer.group_by = GroupBy::Tag { key },
+            (None, Some(name)) => self.cost_explorer.group_by = GroupBy::CostCategory { name },
+            (None, None) => {}
+        }
+        self.cost_explorer.region_count = env_parse("", "REGION_COUNT")?.or(self.cost_explorer.region_count);
+        self.cost_explorer.month_to_date_ranking_count = env_parse("", "MONTH_TO_DATE_RANKING_COUNT")?.or(self.cost_explorer.month_to_date_ranking_count);
+        self.cost_explorer.focus_service = env_var("", "FOCUS_SERVICE").or(self.cost_explorer.focus_service);
+        self.cost_explorer.drill_down_count = env_parse("", "DRILL_DOWN_COUNT")?.or(self.cost_explorer.drill_down_count);
+        self.cost_explorer.service_forecast_count = env_parse("", "SERVICE_FORECAST_COUNT")?.or(self.cost_explorer.service_forecast_count);
+        self.cost_explorer.movers_count = env_parse("", "MOVERS_COUNT")?.or(self.cost_explorer.movers_count);
+        if let Some(movers_order) = env_parse("", "MOVERS_ORDER")? {
+            self.cost_explorer.movers_order = movers_order;
+        }
+        if let Some(movers_floor) = env_parse("", "MOVERS_FLOOR_USD")? {
+            self.cost_explorer.movers_floor = movers_floor;
+        }
+        if let Some(month_start_day) = env_parse("", "MONTH_START_DAY")? {
+            self.cost_explorer.month_start_day = month_start_day;
+        }
+        if let Some(filter) = env_var("", "COST_FILTER") {
+            self.cost_explorer.filter = Some(serde_json::from_str(&filter).map_err(|e| format!("設定エラー: COST_FILTER の JSON が不正です: {e}"))?);
+        }
+        if let Some(tax) = env_parse("", "TAX_HANDLING")? {
+            self.cost_explorer.tax = tax;
+        }
+        if let Some(support) = env_parse("", "SUPPORT_HANDLING")? {
+            self.cost_explorer.support = support;
+        }
+        if let Some(marketplace) = env_parse("", "MARKETPLACE_HANDLING")? {
+            self.cost_explorer.marketplace = marketplace;
+        }
+        if let Some(metric) = env_parse("", "COST_METRIC")? {
+            self.cost_explorer.metric = metric;
+        }
+        let section_metrics = &mut self.cost_explorer.section_metrics;
+        section_metrics.daily = env_parse("", "DAILY_COST_METRIC")?.or(section_metrics.daily);
+        section_metrics.monthly = env_parse("", "MONTHLY_COST_METRIC")?.or(section_metrics.monthly);
+        section_metrics.forecast = env_parse("", "FORECAST_COST_METRIC")?.or(section_metrics.forecast);
+        self.cost_explorer.secondary_metric = env_parse("", "SECONDARY_METRIC")?.or(self.cost_explorer.secondary_metric);
+        if let Some(day_offset) = env_parse("", "DAY_OFFSET")? {
+            self.cost_explorer.day_offset = day_offset;
+        }
+        if let Some(count) = env_parse("", "HOURLY_COUNT")? {
+            self.cost_explorer.hourly.get_or_insert_with(HourlyConfig::default).count = count;
+        }
+        if let Some(spike_ratio) = env_parse("", "HOURLY_SPIKE_RATIO")? {
+            self.cost_explorer.hourly.get_or_insert_with(HourlyConfig::default).spike_ratio = spike_ratio;
+        }
+        self.cost_explorer.resource_service = env_var("", "RESOURCE_SERVICE").or(self.cost_explorer.resource_service);
+        if let Some(target) = env_parse("", "SAVINGS_PLANS_COVERAGE_TARGET_PERCENT")? {
+            self.cost_explorer.savings_plans_coverage_target = target;
+        }
+        if let Some(target) = env_parse("", "RESERVATION_UTILIZATION_TARGET_PERCENT")? {
+            self.cost_explorer.reservation_utilization_target = target;
+        }
+        if let Some(days) = env_parse("", "COMMITMENT_EXPIRY_WARNING_DAYS")? {
+            self.cost_explorer.expiry_warning_days = Some(days);
+        }
+        if let Some(count) = env_parse("", "RIGHTSIZING_COUNT")? {
+            self.cost_explorer.rightsizing_count = Some(count);
+        }
+        if let Some(count) = env_parse("", "LAMBDA_FUNCTION_COUNT")? {
+            self.cost_explorer.lambda_function_count = Some(count);
+        }
+        self.cost_explorer.tag_compliance_key = env_var("", "TAG_COMPLIANCE_KEY").or(self.cost_explorer.tag_compliance_key);
+        self.cost_explorer.environment_tag_key = env_var("", "ENVIRONMENT_TAG_KEY").or(self.cost_explorer.environment_tag_key);
+        self.cur = CurConfig::from_env()?.or(self.cur);
+        if let Some(forecast) = env_parse("", "INCLUDE_FORECAST")? {
+            self.sections.forecast = forecast;
+        }
+        if let Some(month_composition) = env_parse("", "INCLUDE_MONTH_COMPOSITION")? {
+            self.sections.month_composition = month_composition;
+        }
+        if let Some(quarter_forecast) = env_parse("", "INCLUDE_QUARTER_FORECAST")? {
+            self.sections.quarter_forecast = quarter_forecast;
+        }
+        if let Some(year_forecast) = env_parse("", "INCLUDE_YEAR_FORECAST")? {
+            self.sections.year_forecast = year_forecast;
+        }
+        if let Some(year_over_year) = env_parse("", "INCLUDE_YEAR_OVER_YEAR")? {
+            self.sections.year_over_year = year_over_year;
+        }
+        if let Some(moving_average) = env_parse("", "INCLUDE_MOVING_AVERAGE")? {
+            self.sections.moving_average = moving_average;
+        }
+        self.feature_flags = AppConfigSource::from_env()?.or(self.feature_flags);
+        self.message_prefix = env_var("", "MESSAGE_PREFIX").or(self.message_prefix);
+        if let Some(enabled) = env_parse("", "NOTIFICATIONS_ENABLED")? {
+            self.notifications_enabled = enabled;
+        }
+        self.with_flag_overrides(|name| env_var("", name))
+    }
+
+    /// 真偽値の設定を `lookup` で引いた値で上書きする。値のない項目は元の設定のまま
+    fn with_flag_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<Self, MyError> {
+        let flag = |name: &str, current: bool| -> Result<bool, MyError> { Ok(parse_var("", name, lookup(name))?.unwrap_or(current)) };
+        self.ranking_medals = flag("RANKING_MEDALS", self.ranking_medals)?;
+        self.idle_resources = flag("INCLUDE_IDLE_RESOURCES", self.idle_resources)?;
+        self.cost_explorer.exclude_credits = flag("EXCLUDE_CREDITS", self.cost_explorer.exclude_credits)?;
+        self.cost_explorer.anomalies = flag("INCLUDE_ANOMALIES", self.cost_explorer.anomalies)?;
+        self.cost_explorer.savings_plans_utilization = flag("INCLUDE_SAVINGS_PLANS_UTILIZATION", self.cost_explorer.savings_plans_utilization)?;
+        self.cost_explorer.savings_plans_coverage = flag("INCLUDE_SAVINGS_PLANS_COVERAGE", self.cost_explorer.savings_plans_coverage)?;
+        self.cost_explorer.reservations = flag("INCLUDE_RESERVATIONS", self.cost_explorer.reservations)?;
+        self.cost_explorer.purchase_recommendations = flag("INCLUDE_PURCHASE_RECOMMENDATIONS", self.cost_explorer.purchase_recommendations)?;
+        self.cost_explorer.purchase_option_breakdown = flag("INCLUDE_PURCHASE_OPTIONS", self.cost_explorer.purchase_option_breakdown)?;
+        self.cost_explorer.data_transfer = flag("INCLUDE_DATA_TRANSFER", self.cost_explorer.data_transfer)?;
+        self.cost_explorer.top_service_usage_types = flag("INCLUDE_TOP_SERVICE_USAGE_TYPES", self.cost_explorer.top_service_usage_types)?;
+        self.cost_explorer.cloudwatch_usage = flag("INCLUDE_CLOUDWATCH_USAGE", self.cost_explorer.cloudwatch_usage)?;
+        self.cost_explorer.ai_spotlight = flag("INCLUDE_AI_SPOTLIGHT", self.cost_explorer.ai_spotlight)?;
+        self.cost_explorer.compare_previous = flag("COMPARE_PREVIOUS", self.cost_explorer.compare_previous)?;
+        self.cost_explorer.daily_chart = flag("INCLUDE_DAILY_CHART", self.cost_explorer.daily_chart)?;
+        self.cost_explorer.rank_changes = flag("SHOW_RANK_CHANGES", self.cost_explorer.rank_changes)?;
+        self.cost_explorer.compare_last_week = flag("COMPARE_LAST_WEEK", self.cost_explorer.compare_last_week)?;
+        self.cost_explorer.compare_last_month = flag("COMPARE_LAST_MONTH", self.cost_explorer.compare_last_month)?;
+        self.cost_explorer.detect_new_services = flag("DETECT_NEW_SERVICES", self.cost_explorer.detect_new_services)?;
+        self.cost_explorer.detect_disappeared_services = flag("DETECT_DISAPPEARED_SERVICES", self.cost_explorer.detect_disappeared_services)?;
+        self.dry_run = flag("DRY_RUN", self.dry_run)?;
+        Ok(self)
+    }
+
+    /// 値の範囲や組み合わせを検証する
+    pub fn validate(&self) -> Result<(), MyError> {
+        if !(1..=MAX_DISPLAY_COUNT).contains(&self.display_count) {
+            return Err(format!("設定エラー: display_count は 1 から {MAX_DISPLAY_COUNT} の範囲にしてください ({})", self.display_count).into());
+        }
+        if self.currency.len() != 3 || !self.currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(format!("設定エラー: currency は 3 文字の通貨コードにしてください ({})", self.currency).into());
+        }
+        if let Some(budget) = self.monthly_budget.filter(|budget| *budget <= 0.0) {
+            return Err(format!("設定エラー: monthly_budget は正の値にしてください ({budget})").into());
+        }
+        if let Some((service, budget)) = self.service_budgets.iter().find(|(_, budget)| **budget <= 0.0) {
+            return Err(format!("設定エラー: service_budgets の {service} は正の値にしてください ({budget})").into());
+        }
+        if !self.service_budgets.is_empty() && self.cost_explorer.group_by != GroupBy::Service {
+            return Err("設定エラー: service_budgets はサービス別に集計するときだけ設定できます".into());
+        }
+        if let Some(budgets) = self.budgets.as_ref().filter(|budgets| budgets.warning_percent <= 0.0) {
+            return Err(format!("設定エラー: budgets の warning_percent は正の値にしてください ({})", budgets.warning_percent).into());
+        }
+        if let Some(window) = self.cost_explorer.window.filter(|window| window.start >= window.end) {
+            return Err(format!("設定エラー: 集計期間の開始日 {} は終了日 {} より前にしてください", window.start, window.end).into());
+        }
+        if self.cost_explorer.day_offset == 0 {
+            return Err("設定エラー: day_offset は 1 以上にしてください (今日の料金はまだ集計されていません)".into());
+        }
+        if let Some(hourly) = self.cost_explorer.hourly.filter(|hourly| hourly.count == 0 || hourly.spike_ratio <= 1.0) {
+            return Err(format!("設定エラー: hourly の count は 1 以上、spike_ratio は 1 より大きくしてください ({hourly:?})").into());
+        }
+        if self.cost_explorer.region_count == Some(0) {
+            return Err("設定エラー: region_count は 1 以上にしてください".into());
+        }
+        if self.cost_explorer.month_to_date_ranking_count == Some(0) {
+            return Err("設定エラー: month_to_date_ranking_count は 1 以上にしてください".into());
+        }
+        if let Some(drill_down_count) = self.cost_explorer.drill_down_count {
+            if drill_down_count == 0 {
+                return Err("設定エラー: drill_down_count は 1 以上にしてください".into());
+            }
+            if self.cost_explorer.group_by != GroupBy::Service {
+                return Err("設定エラー: drill_down_count はサービス別に集計するときだけ設定できます".into());
+            }
+        }
+        if self.cost_explorer.top_service_usage_types && self.cost_explorer.group_by != GroupBy::Service {
+            return Err("設定エラー: top_service_usage_types はサービス別に集計するときだけ設定できます".into());
+        }
+        if let Some(service_forecast_count) = self.cost_explorer.service_forecast_count {
+            if service_forecast_count == 0 {
+                return Err("設定エラー: service_forecast_count は 1 以上にしてください".into());
+            }
+            if self.cost_explorer.group_by != GroupBy::Service {
+                return Err("設定エラー: service_forecast_count はサービス別に集計するときだけ設定できます".into());
+            }
+        }
+        if self.cost_explorer.movers_count == Some(0) {
+            return Err("設定エラー: movers_count は 1 以上にしてください".into());
+        }
+        if self.ranking_floor < 0.0 {
+            return Err(format!("設定エラー: ranking_floor は 0 以上にしてください ({})", self.ranking_floor).into());
+        }
+        if self.cost_explorer.movers_floor < 0.0 {
+            return Err(format!("設定エラー: movers_floor は 0 以上にしてください ({})", self.cost_explorer.movers_floor).into());
+        }
+        if !(0.0..=100.0).contains(&self.cost_explorer.savings_plans_coverage_target) {
+            return Err(format!("設定エラー: savings_plans_coverage_target は 0〜100 にしてください ({})", self.cost_explorer.savings_plans_coverage_target).into());
+        }
+        if !(0.0..=100.0).contains(&self.cost_explorer.reservation_utilization_target) {
+            return Err(format!("設定エラー: reservation_utilization_target は 0〜100 にしてください ({})", self.cost_explorer.reservation_utilization_target).into());
+        }
+        if self.log_group_count == Some(0) {
+            return Err("設定エラー: log_group_count は 1 以上にしてください".into());
+        }
+        if self.cost_explorer.lambda_function_count == Some(0) {
+            return Err("設定エラー: lambda_function_count は 1 以上にしてください".into());
+        }
+        if self.cost_explorer.rightsizing_count == Some(0) {
+            return Err("設定エラー: rightsizing_count は 1 以上にしてください".into());
+        }
+        if self.cost_explorer.expiry_warning_days == Some(0) {
+            return Err("設定エラー: expiry_warning_days は 1 以上にしてください".into());
+        }
+        if !(1..=28).contains(&self.cost_explorer.month_start_day) {
+            return Err(format!("設定エラー: month_start_day は 1〜28 にしてください ({})", self.cost_explorer.month_start_day).into());
+        }
+        if self.provider == ProviderKind::Cur && self.cur.is_none() {
+            return Err("設定エラー: provider を cur にする場合は cur (CUR_DATABASE) も必要です".into());
+        }
+        if self.cur.as_ref().is_some_and(|cur| cur.s3_bucket_count == Some(0)) {
+            return Err("設定エラー: cur の s3_bucket_count は 1 以上にしてください".into());
+        }
+        self.thresholds.validate()?;
+        if let Some(quiet) = self.quiet.as_ref().filter(|quiet| quiet.floor <= 0.0 || quiet.tolerance_percent < 0.0) {
+            return Err(format!("設定エラー: quiet の floor は正の値、tolerance_percent は 0 以上にしてください ({quiet:?})").into());
+        }
+        if self.pagerduty.is_some() && self.monthly_budget.is_none() {
+            return Err("設定エラー: PagerDuty を使う場合は monthly_budget (MONTHLY_BUDGET_USD) も必要です".into());
+        }
+        Ok(())
+    }
+}
+
+/// `{prefix}{name}` の環境変数を返す。未設定または空なら `None`
+pub(crate) fn env_var(prefix: &str, name: &str) -> Option<String> {
+    std::env::var(format!("{prefix}{name}")).ok().filter(|value| !value.is_empty())
+}
+
+/// `{prefix}{name}` の環境変数をパースする。未設定なら `None`、パースできなければエラー
+pub(crate) fn env_parse<T>(prefix: &str, name: &str) -> Result<Option<T>, MyError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    parse_var(prefix, name, env_var(prefix, name))
+}
+
+/// `{prefix}{name}` の値 `value` をパースする。値がなければ `None`、パースできなければエラー
+fn parse_var<T>(prefix: &str, name: &str, value: Option<String>) -> Result<Option<T>, MyError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .map(|value| value.parse::<T>().map_err(|e| format!("設定エラー: {prefix}{name} の値が不正です: {value} ({e})").into()))
+        .transpose()
+}
+
+/// `{prefix}{name}` の環境変数を真偽値として読む。未設定なら `false`
+pub(crate) fn env_flag(prefix: &str, name: &str) -> Result<bool, MyError> {
+    Ok(env_parse(prefix, name)?.unwrap_or(false))
+}
+
+/// `{prefix}{name}` が設定されていなければ、`{prefix}{required_by}` と組み合わせて必要な旨のエラーを返す
+pub(crate) fn env_required(prefix: &str, name: &str, required_by: &str) -> Result<String, MyError> {
+    env_var(prefix, name)
+        .ok_or_else(|| format!("設定エラー: {prefix}{required_by} を設定する場合は {prefix}{name} も必要です").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(Config::default().validate().is_ok());
+        assert!(Config { display_count: 0, ..Config::default() }.validate().is_err());
+        assert!(Config { display_count: MAX_DISPLAY_COUNT + 1, ..Config::default() }.validate().is_err());
+        assert!(Config { currency: "yen".to_string(), ..Config::default() }.validate().is_err());
+        assert!(Config { monthly_budget: Some(-1.0), ..Config::default() }.validate().is_err());
+        let today = CostExplorerConfig { day_offset: 0, ..CostExplorerConfig::default() };
+        assert!(Config { cost_explorer: today, ..Config::default() }.validate().is_err());
+        let by_tag = CostExplorerConfig { group_by: GroupBy::Tag { key: "Project".to_string() }, drill_down_count: Some(3), ..CostExplorerConfig::default() };
+        assert!(Config { cost_explorer: by_tag, ..Config::default() }.validate().is_err());
+        assert!(Config { provider: ProviderKind::Cur, ..Config::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_message_prefix() {
+        assert_eq!(Config::default().message_prefix(), None);
+        let config = Config { environment: Some("prod".to_string()), ..Config::default() };
+        assert_eq!(config.message_prefix().as_deref(), Some("[prod]"));
+        let config = Config { message_prefix: Some(":rotating_light:".to_string()), ..config };
+        assert_eq!(config.message_prefix().as_deref(), Some(":rotating_light:"));
+    }
+
+    #[test]
+    fn test_flag_overrides() {
+        let values = HashMap::from([("DRY_RUN", "false"), ("RANKING_MEDALS", "false"), ("EXCLUDE_CREDITS", "true")]);
+        let lookup = |name: &str| values.get(name).map(|value| value.to_string());
+        let config = Config { dry_run: true, ranking_medals: true, ..Config::default() }.with_flag_overrides(lookup).unwrap();
+        assert!(!config.dry_run);
+        assert!(!config.ranking_medals);
+        assert!(config.cost_explorer.exclude_credits);
+        assert!(!config.idle_resources);
+
+        let values = HashMap::from([("DRY_RUN", "yes")]);
+        assert!(Config::default().with_flag_overrides(|name| values.get(name).map(|value| value.to_string())).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use aws_sdk_ssm as ssm;
+use aws_sdk_ssm::types::{Parameter, ParameterType};
+use serde_json::{Map, Value};
+
+use crate::MyError;
+use crate::config::Config;
+
+/// SSM Parameter Store のパスから設定を読み込む。
+/// パスそのものにパラメータがあれば設定全体の JSON として、なければ配下のパラメータを 1 項目ずつ読み込む
+/// (例: `{path}/display_count`、`{path}/channels/slack/webhook_url`)
+pub async fn load(path: &str) -> Result<Config, MyError> {
+    let sdk_config = aws_config::load_from_env().await;
+    let client = ssm::Client::new(&sdk_config);
+    let path = path.trim_end_matches('/');
+
+    let value = match get_parameter(&client, path).await? {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("設定エラー: {path} の JSON が不正です: {e}"))?,
+        None => {
+            let parameters = client.get_parameters_by_path()
+                .path(path)
+                .recursive(true)
+                .with_decryption(true)
+                .into_paginator()
+                .items()
+                .send()
+                .try_collect()
+                .await?;
+            to_tree(path, &parameters)?
+        }
+    };
+    serde_json::from_value(value).map_err(|e| format!("設定エラー: {path} の設定を読み込めません: {e}").into())
+}
+
+/// パラメータの値を返す。存在しなければ `None`
+async fn get_parameter(client: &ssm::Client, name: &str) -> Result<Option<String>, MyError> {
+    match client.get_parameter().name(name).with_decryption(true).send().await {
+        Ok(output) => Ok(output.parameter.and_then(|parameter| parameter.value)),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_parameter_not_found()) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `{path}` からの相対名を `/` で区切ったキーとして、パラメータを入れ子の JSON にまとめる
+fn to_tree(path: &str, parameters: &[Parameter]) -> Result<Value, MyError> {
+    let mut root = Map::new();
+    for parameter in parameters {
+        let (Some(name), Some(value)) = (parameter.name(), parameter.value()) else {
+            continue;
+        };
+        let Some(relative) = name.strip_prefix(path).map(|relative| relative.trim_start_matches('/')) else {
+            continue;
+        };
+        let keys: Vec<&str> = relative.split('/').collect();
+        let Some((leaf, parents)) = keys.split_last() else {
+            continue;
+        };
+        let mut node = &mut root;
+        for key in parents {
+            node = node.entry(*key)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .ok_or_else(|| format!("設定エラー: {name} の上位の {key} に値が設定されています"))?;
+        }
+        node.insert(leaf.to_string(), leaf_value(parameter.r#type(), value));
+    }
+    Ok(Value::Object(root))
+}
+
+/// StringList は文字列の配列に、それ以外は JSON として読めればその値に、読めなければ文字列にする。
+/// 数字だけの文字列 (Chatwork のルーム ID など) は `"123"` のように引用符で囲んで登録する
+fn leaf_value(parameter_type: Option<&ParameterType>, value: &str) -> Value {
+    match parameter_type {
+        Some(ParameterType::StringList) => value.split(',').map(|item| Value::String(item.trim().to_string())).collect(),
+        _ => serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn parameter(name: &str, value: &str, parameter_type: ParameterType) -> Parameter {
+        Parameter::builder().name(name).value(value).r#type(parameter_type).build()
+    }
+
+    #[test]
+    fn test_to_tree() {
+        let parameters = vec![
+            parameter("/billing/display_count", "10", ParameterType::String),
+            parameter("/billing/channels/slack/webhook_url", "https://hooks.slack.com/services/T000/B000/XXXX", ParameterType::SecureString),
+            parameter("/billing/channels/email/to", "a@example.com, b@example.com", ParameterType::StringList),
+        ];
+        assert_eq!(to_tree("/billing", &parameters).unwrap(), json!({
+            "display_count": 10,
+            "channels": {
+                "slack": { "webhook_url": "https://hooks.slack.com/services/T000/B000/XXXX" },
+                "email": { "to": ["a@example.com", "b@example.com"] },
+            },
+        }));
+    }
+}
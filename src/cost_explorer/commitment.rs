@@ -0,0 +1,237 @@
+use chrono::NaiveDate;
+
+use crate::report::{Breakdown, ServiceCost};
+
+/// Savings Plans のコミットメントの利用状況 (USD)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SavingsPlansUtilization {
+    /// 使ったコミットメント
+    pub used_commitment: f64,
+    /// 使われずに無駄になったコミットメント
+    pub unused_commitment: f64,
+    /// 利用率 (%)
+    pub utilization_percent: f64,
+    /// オンデマンドと比べた正味の節約額。コミットメントの料金を差し引いてある
+    pub net_savings: f64,
+}
+
+impl SavingsPlansUtilization {
+    /// 利用率・未使用のコミットメント・正味の節約額を内訳として返す。`period` は集計期間の名前
+    pub fn to_breakdown(&self, period: &str) -> Breakdown {
+        Breakdown {
+            title: format!("Savings Plans の利用状況 ({period})"),
+            items: vec![
+                ServiceCost::new(format!("使用したコミットメント (利用率 {:.1}%)", self.utilization_percent), self.used_commitment),
+                ServiceCost::new("未使用のコミットメント".to_string(), self.unused_commitment),
+                ServiceCost::new("正味の節約額".to_string(), self.net_savings),
+            ],
+        }
+    }
+}
+
+/// Savings Plans の対象になる料金のうち、Savings Plans でまかなわれた割合 (USD)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SavingsPlansCoverage {
+    /// Savings Plans でまかなわれた料金
+    pub covered: f64,
+    /// オンデマンドで払った料金
+    pub on_demand: f64,
+}
+
+impl SavingsPlansCoverage {
+    /// カバー率 (%)。対象の料金がなければ `None`
+    pub fn percent(&self) -> Option<f64> {
+        let total = self.covered + self.on_demand;
+        (total > 0.0).then(|| self.covered / total * 100.0)
+    }
+
+    /// カバーされた料金とオンデマンドの料金を内訳として返す。カバー率が `target_percent` を下回れば見出しとオンデマンドの行に目印を付ける
+    pub fn to_breakdown(&self, period: &str, target_percent: f64) -> Breakdown {
+        let percent = self.percent().unwrap_or(0.0);
+        let below_target = self.percent().is_some_and(|percent| percent < target_percent);
+        let on_demand = if below_target {
+            format!("オンデマンド ({:.1}%) ⚠ カバー率が目標の {target_percent:.0}% を下回っています", 100.0 - percent)
+        } else {
+            format!("オンデマンド ({:.1}%)", 100.0 - percent)
+        };
+        Breakdown {
+            title: format!("Savings Plans のカバー率 ({period}){}", if below_target { " ⚠" } else { "" }),
+            items: vec![
+                ServiceCost::new(format!("Savings Plans でカバー ({percent:.1}%)"), self.covered),
+                ServiceCost::new(on_demand, self.on_demand),
+            ],
+        }
+    }
+}
+
+/// 1 つのリザーブドインスタンスの利用状況
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReservationUtilization {
+    /// 表示名 (例: `m5.large us-east-1 (123456789)`)
+    pub name: String,
+    /// 期限の日
+    pub end_date: Option<NaiveDate>,
+    /// 購入した時間数
+    pub purchased_hours: f64,
+    /// 実際に使われた時間数
+    pub used_hours: f64,
+    /// 期間に割り当てた料金 (USD)
+    pub amortized_fee: f64,
+}
+
+impl ReservationUtilization {
+    /// 利用率 (%)。購入した時間がなければ `None`
+    pub fn percent(&self) -> Option<f64> {
+        (self.purchased_hours > 0.0).then(|| self.used_hours / self.purchased_hours * 100.0)
+    }
+
+    fn is_underutilized(&self, target_percent: f64) -> bool {
+        self.percent().is_some_and(|percent| percent < target_percent)
+    }
+}
+
+/// リザーブドインスタンス全体の利用率・カバー率と、予約ごとの利用率を利用率の低い順に並べた内訳を返す。
+/// 利用率が `target_percent` を下回る予約に目印を付ける
+pub fn reservations_breakdown(period: &str, mut reservations: Vec<ReservationUtilization>, coverage_percent: Option<f64>, target_percent: f64) -> Breakdown {
+    let total = ReservationUtilization {
+        name: String::new(),
+        end_date: None,
+        purchased_hours: reservations.iter().map(|reservation| reservation.purchased_hours).sum(),
+        used_hours: reservations.iter().map(|reservation| reservation.used_hours).sum(),
+        amortized_fee: reservations.iter().map(|reservation| reservation.amortized_fee).sum(),
+    };
+    let coverage = coverage_percent.map(|percent| format!(" / カバー率 {percent:.1}%")).unwrap_or_default();
+    let mut items = vec![ServiceCost::new(format!("全体 (利用率 {:.1}%{coverage})", total.percent().unwrap_or(0.0)), total.amortized_fee)];
+    reservations.sort_by(|a, b| a.percent().unwrap_or(0.0).total_cmp(&b.percent().unwrap_or(0.0)));
+    let underutilized = reservations.iter().any(|reservation| reservation.is_underutilized(target_percent));
+    items.extend(reservations.into_iter().map(|reservation| {
+        let marker = if reservation.is_underutilized(target_percent) { " ⚠ 利用率が低い" } else { "" };
+        ServiceCost::new(format!("{} (利用率 {:.1}%){marker}", reservation.name, reservation.percent().unwrap_or(0.0)), reservation.amortized_fee)
+    }));
+    Breakdown {
+        title: format!("リザーブドインスタンスの利用状況 ({period}){}", if underutilized { " ⚠" } else { "" }),
+        items,
+    }
+}
+
+/// 期限のある予約または Savings Plans
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expiring {
+    pub name: String,
+    pub end_date: NaiveDate,
+    /// 1 か月あたりの料金 (USD)。期限が切れるとこの分の割引がなくなる
+    pub monthly_cost: f64,
+}
+
+/// 今日から `days` 日以内に期限が切れるものを期限の近い順に並べ、月額の合計を先頭に置いた内訳を返す。なければ `None`
+pub fn expiry_breakdown(today: NaiveDate, days: u32, commitments: Vec<Expiring>) -> Option<Breakdown> {
+    let deadline = today + chrono::Duration::days(days.into());
+    let mut expiring: Vec<_> = commitments.into_iter().filter(|commitment| (today..=deadline).contains(&commitment.end_date)).collect();
+    if expiring.is_empty() {
+        return None;
+    }
+    expiring.sort_by_key(|commitment| commitment.end_date);
+    let mut items = vec![ServiceCost::new("合計".to_string(), expiring.iter().map(|commitment| commitment.monthly_cost).sum())];
+    items.extend(expiring.iter().map(|commitment| {
+        let remaining = (commitment.end_date - today).num_days();
+        ServiceCost::new(format!("{}: {} まで (あと {remaining} 日)", commitment.name, commitment.end_date), commitment.monthly_cost)
+    }));
+    Some(Breakdown { title: format!("{days} 日以内に期限が切れる RI・Savings Plans {} 件の月額 ⚠", expiring.len()), items })
+}
+
+/// 購入を勧められた Savings Plans または予約
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    /// 表示名。勧められたコミットメントや件数を添える
+    pub name: String,
+    /// 1 か月あたりの節約見込み (USD)
+    pub monthly_savings: f64,
+}
+
+/// 節約が見込める推奨を節約額の大きい順に並べた内訳を返す。なければ `None`
+pub fn recommendations_breakdown(recommendations: Vec<Recommendation>) -> Option<Breakdown> {
+    top_savings("購入の推奨 (1 年・前払いなし) の月あたりの節約見込み", recommendations, usize::MAX)
+}
+
+/// 節約額の大きい順に `count` 件の適正化の推奨を並べた内訳を返す。なければ `None`
+pub fn rightsizing_breakdown(recommendations: Vec<Recommendation>, count: usize) -> Option<Breakdown> {
+    top_savings("EC2 インスタンスの適正化の推奨と月あたりの節約見込み", recommendations, count)
+}
+
+fn top_savings(title: &str, recommendations: Vec<Recommendation>, count: usize) -> Option<Breakdown> {
+    let mut items: Vec<_> = recommendations.into_iter()
+        .filter(|recommendation| recommendation.monthly_savings > 0.0)
+        .map(|recommendation| ServiceCost::new(recommendation.name, recommendation.monthly_savings))
+        .collect();
+    if items.is_empty() {
+        return None;
+    }
+    items.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    items.truncate(count);
+    Some(Breakdown { title: title.to_string(), items })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_savings_plans_utilization() {
+        let utilization = SavingsPlansUtilization { used_commitment: 92.0, unused_commitment: 8.0, utilization_percent: 92.0, net_savings: 30.5 };
+        let breakdown = utilization.to_breakdown("2026-10-01〜2026-10-15");
+        assert_eq!(breakdown.title, "Savings Plans の利用状況 (2026-10-01〜2026-10-15)");
+        let names: Vec<_> = breakdown.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["使用したコミットメント (利用率 92.0%)", "未使用のコミットメント", "正味の節約額"]);
+        assert_eq!(breakdown.items[1].amount, 8.0);
+    }
+
+    #[test]
+    fn test_savings_plans_coverage() {
+        let coverage = SavingsPlansCoverage { covered: 65.0, on_demand: 35.0 };
+        assert_eq!(coverage.percent(), Some(65.0));
+        let breakdown = coverage.to_breakdown("2026-10", 80.0);
+        assert_eq!(breakdown.title, "Savings Plans のカバー率 (2026-10) ⚠");
+        assert_eq!(breakdown.items[0].name, "Savings Plans でカバー (65.0%)");
+        assert_eq!(breakdown.items[1].name, "オンデマンド (35.0%) ⚠ カバー率が目標の 80% を下回っています");
+        let breakdown = coverage.to_breakdown("2026-10", 60.0);
+        assert_eq!(breakdown.title, "Savings Plans のカバー率 (2026-10)");
+        assert_eq!(breakdown.items[1].name, "オンデマンド (35.0%)");
+        assert_eq!(SavingsPlansCoverage { covered: 0.0, on_demand: 0.0 }.percent(), None);
+    }
+
+    #[test]
+    fn test_reservations_breakdown() {
+        let reservation = |name: &str, used_hours| ReservationUtilization { name: name.to_string(), end_date: None, purchased_hours: 100.0, used_hours, amortized_fee: 10.0 };
+        let breakdown = reservations_breakdown("2026-10", vec![reservation("m5.large", 95.0), reservation("r5.xlarge", 45.0)], Some(60.0), 80.0);
+        assert_eq!(breakdown.title, "リザーブドインスタンスの利用状況 (2026-10) ⚠");
+        let names: Vec<_> = breakdown.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["全体 (利用率 70.0% / カバー率 60.0%)", "r5.xlarge (利用率 45.0%) ⚠ 利用率が低い", "m5.large (利用率 95.0%)"]);
+        assert_eq!(breakdown.items[0].amount, 20.0);
+    }
+
+    #[test]
+    fn test_expiry_breakdown() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let expiring = |name: &str, end_date, monthly_cost| Expiring { name: name.to_string(), end_date, monthly_cost };
+        let commitments = vec![expiring("RI r5.xlarge", date(30), 300.0), expiring("RI m5.large", date(20), 120.0), expiring("Savings Plans Compute", date(31), 500.0)];
+        let breakdown = expiry_breakdown(date(16), 14, commitments.clone()).unwrap();
+        assert_eq!(breakdown.title, "14 日以内に期限が切れる RI・Savings Plans 2 件の月額 ⚠");
+        let names: Vec<_> = breakdown.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["合計", "RI m5.large: 2026-10-20 まで (あと 4 日)", "RI r5.xlarge: 2026-10-30 まで (あと 14 日)"]);
+        assert_eq!(breakdown.items[0].amount, 420.0);
+        assert_eq!(expiry_breakdown(date(16), 3, commitments), None);
+    }
+
+    #[test]
+    fn test_recommendations_breakdown() {
+        let recommendation = |name: &str, monthly_savings| Recommendation { name: name.to_string(), monthly_savings };
+        let breakdown = recommendations_breakdown(vec![recommendation("RI RDS (1 件)", 40.0), recommendation("RI EC2", 0.0), recommendation("Compute Savings Plans", 120.0)]).unwrap();
+        let names: Vec<_> = breakdown.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["Compute Savings Plans", "RI RDS (1 件)"]);
+        assert_eq!(recommendations_breakdown(vec![recommendation("RI EC2", 0.0)]), None);
+
+        let breakdown = rightsizing_breakdown(vec![recommendation("web-1 (m5.xlarge): m5.large に変更", 50.0), recommendation("batch-1 (c5.2xlarge): 停止", 200.0)], 1).unwrap();
+        assert_eq!(breakdown.items.len(), 1);
+        assert_eq!(breakdown.items[0].name, "batch-1 (c5.2xlarge): 停止");
+    }
+}
@@ -0,0 +1,132 @@
+use aws_sdk_costexplorer::types::{CostCategoryValues, Dimension, DimensionValues, Expression, MatchOption, TagValues};
+use serde::Deserialize;
+
+/// Cost Explorer のフィルタ式。API の `Expression` と同じ JSON の形で書く
+/// (例: `{"Not": {"Dimensions": {"Key": "LINKED_ACCOUNT", "Values": ["111111111111"]}}}`)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct Filter {
+    #[serde(default)]
+    pub and: Vec<Filter>,
+    #[serde(default)]
+    pub or: Vec<Filter>,
+    pub not: Option<Box<Filter>>,
+    pub dimensions: Option<FilterValues>,
+    pub tags: Option<FilterValues>,
+    pub cost_categories: Option<FilterValues>,
+}
+
+/// ディメンション・タグ・コストカテゴリのキーと値
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct FilterValues {
+    pub key: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+    #[serde(default)]
+    pub match_options: Vec<String>,
+}
+
+impl Filter {
+    pub fn to_expression(&self) -> Expression {
+        let non_empty = |filters: &[Filter]| (!filters.is_empty()).then(|| filters.iter().map(Filter::to_expression).collect());
+        Expression::builder()
+            .set_and(non_empty(&self.and))
+            .set_or(non_empty(&self.or))
+            .set_not(self.not.as_ref().map(|not| Box::new(not.to_expression())))
+            .set_dimensions(self.dimensions.as_ref().map(|dimensions| {
+                DimensionValues::builder()
+                    .key(Dimension::from(dimensions.key.as_str()))
+                    .set_values(Some(dimensions.values.clone()))
+                    .set_match_options(Some(dimensions.match_options()))
+                    .build()
+            }))
+            .set_tags(self.tags.as_ref().map(|tags| {
+                TagValues::builder()
+                    .key(&tags.key)
+                    .set_values(Some(tags.values.clone()))
+                    .set_match_options(Some(tags.match_options()))
+                    .build()
+            }))
+            .set_cost_categories(self.cost_categories.as_ref().map(|cost_categories| {
+                CostCategoryValues::builder()
+                    .key(&cost_categories.key)
+                    .set_values(Some(cost_categories.values.clone()))
+                    .set_match_options(Some(cost_categories.match_options()))
+                    .build()
+            }))
+            .build()
+    }
+}
+
+impl FilterValues {
+    fn match_options(&self) -> Vec<MatchOption> {
+        self.match_options.iter().map(|option| MatchOption::from(option.as_str())).collect()
+    }
+}
+
+/// 2 つのフィルタを両方満たす式にする
+pub fn and(a: Option<Expression>, b: Option<Expression>) -> Option<Expression> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Expression::builder().and(a).and(b).build()),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 指定したレコードタイプ (`Credit`・`Tax` など) だけに絞る式
+pub fn record_types(record_types: &[&str]) -> Expression {
+    let record_types = DimensionValues::builder()
+        .key(Dimension::RecordType)
+        .set_values(Some(record_types.iter().map(|record_type| record_type.to_string()).collect()))
+        .build();
+    Expression::builder().dimensions(record_types).build()
+}
+
+/// 指定したレコードタイプを除く式
+pub fn exclude_record_types(types: &[&str]) -> Expression {
+    Expression::builder().not(record_types(types)).build()
+}
+
+/// 指定した請求元 (`AWS Marketplace` など) だけに絞る式
+pub fn billing_entity(entity: &str) -> Expression {
+    Expression::builder()
+        .dimensions(DimensionValues::builder().key(Dimension::BillingEntity).values(entity).build())
+        .build()
+}
+
+/// 指定した請求元を除く式
+pub fn exclude_billing_entity(entity: &str) -> Expression {
+    Expression::builder().not(billing_entity(entity)).build()
+}
+
+/// 指定したサービスだけに絞る式
+pub fn service(service: &str) -> Expression {
+    Expression::builder()
+        .dimensions(DimensionValues::builder().key(Dimension::Service).values(service).build())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_expression() {
+        let filter: Filter = serde_json::from_str(r#"{
+            "Not": { "Dimensions": { "Key": "LINKED_ACCOUNT", "Values": ["111111111111"] } }
+        }"#).unwrap();
+        let expression = filter.to_expression();
+        let dimensions = expression.not.and_then(|not| not.dimensions).unwrap();
+        assert_eq!(dimensions.key, Some(Dimension::LinkedAccount));
+        assert_eq!(dimensions.values, Some(vec!["111111111111".to_string()]));
+
+        assert!(serde_json::from_str::<Filter>(r#"{ "Dimension": {} }"#).is_err());
+    }
+
+    #[test]
+    fn test_exclude_billing_entity() {
+        let dimensions = exclude_billing_entity("AWS Marketplace").not.and_then(|not| not.dimensions).unwrap();
+        assert_eq!(dimensions.key, Some(Dimension::BillingEntity));
+        assert_eq!(dimensions.values, Some(vec!["AWS Marketplace".to_string()]));
+    }
+}
@@ -0,0 +1,56 @@
+use serde::Deserialize;
+
+use crate::report::ServiceCost;
+
+/// 時間単位の料金の内訳の設定。Cost Explorer で時間単位の粒度を有効にしたアカウントで使える
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HourlyConfig {
+    /// 載せる時間帯の数
+    pub count: usize,
+    /// 1 時間の料金が 1 日の平均の何倍を超えたら目立たせるか
+    pub spike_ratio: f64,
+}
+
+impl Default for HourlyConfig {
+    fn default() -> Self {
+        Self { count: 5, spike_ratio: 2.0 }
+    }
+}
+
+impl HourlyConfig {
+    /// 料金の高い順に `count` 件の時間帯を返す。平均から大きく外れた時間帯には倍率を添える
+    pub fn top_hours(&self, hours: Vec<(String, f64)>) -> Vec<ServiceCost> {
+        if hours.is_empty() {
+            return Vec::new();
+        }
+        let average = hours.iter().map(|(_, amount)| amount).sum::<f64>() / hours.len() as f64;
+        let mut hours = hours;
+        hours.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hours.into_iter()
+            .take(self.count)
+            .map(|(hour, amount)| {
+                let name = if average > 0.0 && amount > average * self.spike_ratio {
+                    format!("{hour} ⚠ 平均の {:.1} 倍", amount / average)
+                } else {
+                    hour
+                };
+                ServiceCost::new(name, amount)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_hours() {
+        let hours = (0..24).map(|hour| (format!("{hour:02}:00"), if hour == 3 { 5.0 } else { 1.0 })).collect();
+        let top_hours = HourlyConfig { count: 2, spike_ratio: 2.0 }.top_hours(hours);
+        assert_eq!(top_hours.len(), 2);
+        assert_eq!(top_hours[0].name, "03:00 ⚠ 平均の 4.3 倍");
+        assert!(!top_hours[1].name.contains('⚠'));
+    }
+}
@@ -0,0 +1,1863 @@
+pub mod commitment;
+pub mod filter;
+pub mod hourly;
+pub mod window;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use aws_sdk_costexplorer as costexplorer;
+use aws_sdk_costexplorer::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_costexplorer::operation::get_cost_and_usage::GetCostAndUsageError;
+use aws_sdk_costexplorer::operation::get_cost_forecast::GetCostForecastError;
+use aws_sdk_costexplorer::operation::get_cost_and_usage::builders::GetCostAndUsageFluentBuilder;
+use aws_sdk_costexplorer::types::{AnomalyDateInterval, DateInterval, Expression, Granularity, Group, GroupDefinition, GroupDefinitionType, LookbackPeriodInDays, Metric, MetricValue, PaymentOption, ResultByTime, RightsizingType, RootCause, SupportedSavingsPlansType, TermInYears};
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use futures::future::try_join_all;
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::cost_explorer::commitment::{expiry_breakdown, recommendations_breakdown, reservations_breakdown, rightsizing_breakdown, Expiring, Recommendation, ReservationUtilization, SavingsPlansCoverage, SavingsPlansUtilization};
+use crate::cost_explorer::filter::Filter;
+use crate::cost_explorer::hourly::HourlyConfig;
+use crate::cost_explorer::window::{days_ago_label, Window, WindowGranularity};
+use crate::report::{Breakdown, ForecastInterval, Secondary, ServiceCost, Spotlight};
+
+/// タグが付いていない料金の表示名
+const UNTAGGED: &str = "(タグなし)";
+
+/// コストカテゴリに分類されていない料金の表示名
+const UNCATEGORIZED: &str = "(未分類)";
+
+/// リージョン別の内訳で上位以外をまとめた行の表示名
+const OTHER_REGIONS: &str = "その他のリージョン";
+
+/// 今月のサービス別ランキングで上位以外をまとめた行の表示名
+const OTHER_SERVICES: &str = "その他のサービス";
+
+/// リソース別の内訳に載せるリソース数
+const RESOURCE_COUNT: usize = 10;
+
+/// リソース単位の料金を取得できる日数。Cost Explorer は過去 14 日分だけ保持する
+const RESOURCE_DAYS: i64 = 14;
+
+/// Cost Explorer の料金の通貨単位。レポートの金額はすべて USD として表示通貨に換算する
+const COST_UNIT: &str = "USD";
+
+/// 注目するサービスの内訳に載せる利用タイプ数
+const USAGE_TYPE_COUNT: usize = 10;
+
+/// 今月の予測に添える予測区間の信頼度 (%)
+const PREDICTION_INTERVAL_LEVEL: i32 = 80;
+
+/// 日別のグラフの棒の最大の長さ (文字数)
+const CHART_WIDTH: usize = 20;
+
+/// EC2 インスタンスの料金のサービス名
+const EC2_COMPUTE: &str = "Amazon Elastic Compute Cloud - Compute";
+
+/// 予約の購入の推奨を問い合わせるサービスと表示名
+const RESERVATION_SERVICES: [(&str, &str); 2] = [(EC2_COMPUTE, "EC2"), ("Amazon Relational Database Service", "RDS")];
+
+/// CloudWatch (Logs を含む) の料金のサービス名
+const CLOUDWATCH: &str = "AmazonCloudWatch";
+
+/// Lambda の料金のサービス名
+const LAMBDA: &str = "AWS Lambda";
+
+/// スポットインスタンスの購入オプションの表示名
+const SPOT: &str = "スポット";
+
+/// AWS Marketplace で購入した製品の請求元
+const MARKETPLACE: &str = "AWS Marketplace";
+
+/// AI サービスとみなすサービス名の一部。モデルごとに分かれて請求される Bedrock のサービス (例: `Claude 3.5 Sonnet (Amazon Bedrock Edition)`) も含める
+const AI_SERVICE_KEYWORDS: [&str; 2] = ["Bedrock", "SageMaker"];
+
+/// GPU や機械学習用チップを載せた EC2 インスタンスファミリーの接頭辞
+const ACCELERATED_FAMILIES: [&str; 5] = ["p", "g", "gr", "inf", "trn"];
+
+/// Cost Explorer の問い合わせ方の設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CostExplorerConfig {
+    /// ランキングの集計単位
+    pub group_by: GroupBy,
+    /// リージョン別の内訳に載せる上位のリージョン数。未設定なら内訳を載せない
+    pub region_count: Option<usize>,
+    /// 今月の現時点料金のサービス別ランキングに載せる上位のサービス数。未設定なら載せない
+    pub month_to_date_ranking_count: Option<usize>,
+    /// 利用タイプ別の内訳を載せるサービス (例: `Amazon Elastic Compute Cloud - Compute`)
+    pub focus_service: Option<String>,
+    /// 料金の上位から利用タイプ別の内訳を載せるサービス数。サービス別に集計するときだけ使える
+    pub drill_down_count: Option<usize>,
+    /// 料金の上位から今月末の料金を予測するサービス数 (例: 3)。サービス別に集計するときだけ使える
+    pub service_forecast_count: Option<usize>,
+    /// 直前の同じ長さの期間からの増加額が大きい順に載せるサービス数。未設定なら載せない
+    pub movers_count: Option<usize>,
+    /// 増えたサービスを並べる順
+    pub movers_order: MoversOrder,
+    /// 増加率の順に並べるとき、集計期間の料金がこの額 (USD) 未満のサービスは除く
+    pub movers_floor: f64,
+    /// すべての問い合わせに適用するフィルタ (特定のアカウントだけ、サンドボックスのアカウントを除くなど)
+    pub filter: Option<Filter>,
+    /// クレジット (プロモーションクレジットなど) と返金を除く
+    pub exclude_credits: bool,
+    /// 税金の扱い
+    pub tax: ChargeHandling,
+    /// AWS サポートの料金の扱い。定額の料金が小さなアカウントのランキングの上位を占めるのを避ける
+    pub support: ChargeHandling,
+    /// AWS Marketplace で購入した製品の料金の扱い。第三者に支払うもので、予算も別に立てることが多い
+    pub marketplace: ChargeHandling,
+    /// 料金の指標
+    pub metric: CostMetric,
+    /// セクションごとの指標。未設定のセクションは `metric` を使う
+    pub section_metrics: SectionMetrics,
+    /// ランキングと内訳で料金と並べて表示する指標 (`usage_quantity` か料金の指標)
+    pub secondary_metric: Option<SecondaryMetric>,
+    /// 前々日の代わりに集計する期間。イベントの detail で指定する
+    #[serde(skip)]
+    pub window: Option<Window>,
+    /// 何日前の料金をランキングにするか。昨日 (1) の料金は確定前の見込みの値を含む
+    pub day_offset: u32,
+    /// 昨日の料金の高い時間帯の内訳。未設定なら載せない
+    pub hourly: Option<HourlyConfig>,
+    /// リソース別の内訳を載せるサービス (例: `Amazon Elastic Compute Cloud - Compute`)。
+    /// Cost Explorer でリソース単位のデータを有効にしておく必要がある
+    pub resource_service: Option<String>,
+    /// Cost Anomaly Detection が過去 24 時間に検出した異常を載せる
+    pub anomalies: bool,
+    /// Savings Plans の今月の利用率・未使用のコミットメント・正味の節約額を載せる
+    pub savings_plans_utilization: bool,
+    /// Savings Plans の対象になる今月の料金のうち、Savings Plans でまかなわれた割合を載せる
+    pub savings_plans_coverage: bool,
+    /// Savings Plans のカバー率がこの割合 (%) を下回ったら目立たせる
+    pub savings_plans_coverage_target: f64,
+    /// リザーブドインスタンスの今月の利用率・カバー率と、予約ごとの利用率を載せる
+    pub reservations: bool,
+    /// 予約の利用率がこの割合 (%) を下回ったら目立たせる
+    pub reservation_utilization_target: f64,
+    /// この日数以内に期限が切れるリザーブドインスタンスと Savings Plans を載せる。未設定なら載せない
+    pub expiry_warning_days: Option<u32>,
+    /// Savings Plans と予約の購入の推奨を載せる。推奨は変化が遅いので週次・月次レポートにだけ載せる
+    pub purchase_recommendations: bool,
+    /// EC2 インスタンスの適正化 (サイズ変更・停止) の推奨を節約額の大きい順に載せる件数。未設定なら載せない
+    pub rightsizing_count: Option<usize>,
+    /// EC2 インスタンスの料金をスポット・オンデマンドなどの購入オプション別に分けて、スポットの比率とともに載せる
+    pub purchase_option_breakdown: bool,
+    /// 複数のサービスに分かれて請求されるデータ転送 (インターネットへの転送・AZ 間・NAT ゲートウェイなど) の料金をまとめて載せる
+    pub data_transfer: bool,
+    /// ランキング 1 位のサービスに、料金の高い利用タイプ 3 件の割合を添える。サービス別に集計するときだけ使える
+    pub top_service_usage_types: bool,
+    /// 過去 14 日間で料金の高い Lambda 関数を載せる件数。リソース単位のデータが必要。未設定なら載せない
+    pub lambda_function_count: Option<usize>,
+    /// CloudWatch の料金をログの取り込み・保存・API 呼び出しなどの種類別に分けて載せる
+    pub cloudwatch_usage: bool,
+    /// Bedrock・SageMaker・GPU インスタンスの今月の現時点料金を 1 行にまとめ、先月の同じ日数と比べて載せる
+    pub ai_spotlight: bool,
+    /// 集計期間の料金のうち、このコスト配分タグ (例: `Project`) のない分とその割合を載せる。未設定なら載せない
+    pub tag_compliance_key: Option<String>,
+    /// 集計期間の料金を、このコスト配分タグ (例: `Environment`) の値 (prod・stg・dev など) 別に 1 行にまとめて載せる。未設定なら載せない
+    pub environment_tag_key: Option<String>,
+    /// 合計とサービスごとの料金を、直前の同じ長さの期間 (既定では前日) と比べる
+    pub compare_previous: bool,
+    /// 集計期間の日ごとの料金を棒グラフにして載せる
+    pub daily_chart: bool,
+    /// 直前の同じ長さの期間の順位からの変化をランキングに添える
+    pub rank_changes: bool,
+    /// 合計を 1 週間前の同じ曜日の料金と比べる
+    pub compare_last_week: bool,
+    /// 合計を 1 か月前の同じ日の料金と比べる
+    pub compare_last_month: bool,
+    /// 集計期間の直前 30 日に請求がなかったサービスを載せる
+    pub detect_new_services: bool,
+    /// 集計期間の直前 7 日に請求があり、集計期間に請求がなくなったサービスを載せる
+    pub detect_disappeared_services: bool,
+    /// 月の始まりの日 (1〜28)。21 なら 21 日から翌月 20 日までを 1 か月として今月・先月の料金と予測を集計する
+    pub month_start_day: u32,
+}
+
+/// 料金と並べて表示する指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SecondaryMetric {
+    UsageQuantity,
+    Cost(CostMetric),
+}
+
+impl SecondaryMetric {
+    fn is_cost(self) -> bool {
+        matches!(self, Self::Cost(_))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::UsageQuantity => "UsageQuantity",
+            Self::Cost(metric) => metric.name(),
+        }
+    }
+}
+
+impl FromStr for SecondaryMetric {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "usage_quantity" => Ok(Self::UsageQuantity),
+            other => other.parse().map(Self::Cost),
+        }
+    }
+}
+
+impl TryFrom<String> for SecondaryMetric {
+    type Error = MyError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// セクションごとの料金の指標。一括請求の管理アカウントで配賦に合わせた指標を表示するときに使う
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SectionMetrics {
+    /// 前々日の料金 (ランキングと内訳を含む)
+    pub daily: Option<CostMetric>,
+    /// 今月と先月の料金
+    pub monthly: Option<CostMetric>,
+    /// 今月の予測
+    pub forecast: Option<CostMetric>,
+}
+
+/// 料金の指標。RI や Savings Plans を使うアカウントでは償却後の料金の方が日々の実態に近い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostMetric {
+    #[default]
+    Unblended,
+    Blended,
+    NetUnblended,
+    Amortized,
+    NetAmortized,
+}
+
+impl CostMetric {
+    /// `GetCostAndUsage` の指標名
+    fn name(self) -> &'static str {
+        match self {
+            Self::Unblended => "UnblendedCost",
+            Self::Blended => "BlendedCost",
+            Self::NetUnblended => "NetUnblendedCost",
+            Self::Amortized => "AmortizedCost",
+            Self::NetAmortized => "NetAmortizedCost",
+        }
+    }
+
+    /// ランキングに並べて表示するときの名前
+    fn label(self) -> &'static str {
+        match self {
+            Self::Unblended => "非ブレンド",
+            Self::Blended => "ブレンド",
+            Self::NetUnblended => "割引後の非ブレンド",
+            Self::Amortized => "償却",
+            Self::NetAmortized => "割引後の償却",
+        }
+    }
+
+    /// `GetCostForecast` の指標
+    fn as_forecast_metric(self) -> Metric {
+        match self {
+            Self::Unblended => Metric::UnblendedCost,
+            Self::Blended => Metric::BlendedCost,
+            Self::NetUnblended => Metric::NetUnblendedCost,
+            Self::Amortized => Metric::AmortizedCost,
+            Self::NetAmortized => Metric::NetAmortizedCost,
+        }
+    }
+}
+
+impl FromStr for CostMetric {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "unblended" => Ok(Self::Unblended),
+            "blended" => Ok(Self::Blended),
+            "net_unblended" => Ok(Self::NetUnblended),
+            "amortized" => Ok(Self::Amortized),
+            "net_amortized" => Ok(Self::NetAmortized),
+            other => Err(format!("unblended・blended・net_unblended・amortized・net_amortized のいずれかを指定してください: {other}").into()),
+        }
+    }
+}
+
+/// 日によって計上が偏る料金や定額の料金 (税金・サポートなど) の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChargeHandling {
+    /// ほかの料金と同じく合計やランキングに含める
+    #[default]
+    Include,
+    /// すべての問い合わせから除く
+    Exclude,
+    /// 合計やランキングから除き、別の内訳として載せる
+    Separate,
+}
+
+impl FromStr for ChargeHandling {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "include" => Ok(Self::Include),
+            "exclude" => Ok(Self::Exclude),
+            "separate" => Ok(Self::Separate),
+            other => Err(format!("include・exclude・separate のいずれかを指定してください: {other}").into()),
+        }
+    }
+}
+
+/// 増えたサービスを並べる順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoversOrder {
+    /// 増加額の大きい順
+    #[default]
+    Amount,
+    /// 増加率の大きい順。直前の期間になかったサービスを先にする
+    Percent,
+}
+
+impl FromStr for MoversOrder {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "amount" => Ok(Self::Amount),
+            "percent" => Ok(Self::Percent),
+            other => Err(format!("amount・percent のいずれかを指定してください: {other}").into()),
+        }
+    }
+}
+
+impl CostExplorerConfig {
+    /// ランキングと内訳を集計する期間
+    pub fn window(&self, today: NaiveDate) -> Window {
+        self.window.unwrap_or_else(|| Window::days_ago(today, self.day_offset))
+    }
+
+    /// `today` を含む今月。`month_start_day` 日から 1 か月
+    pub fn current_month(&self, today: NaiveDate) -> Result<Window, MyError> {
+        Window::month_containing(today, self.month_start_day).ok_or_else(|| "Failed to calculate the first day of this month".into())
+    }
+
+    /// 今月の直前の 1 か月
+    pub fn last_month(&self, today: NaiveDate) -> Result<Window, MyError> {
+        let current_month = self.current_month(today)?;
+        Window::month_containing(current_month.start - chrono::Duration::days(1), self.month_start_day).ok_or_else(|| "Failed to calculate the first day of last month".into())
+    }
+
+    /// 今月の初日から昨日まで。今日が月の初日なら先月
+    fn month_to_date(&self, today: NaiveDate) -> Result<Window, MyError> {
+        let current_month = self.current_month(today)?;
+        if current_month.start < today {
+            Ok(Window { end: today, ..current_month })
+        } else {
+            self.last_month(today)
+        }
+    }
+
+    /// レポートに表示する集計期間の名前
+    pub fn period_label(&self) -> String {
+        self.window.map(|window| window.label()).unwrap_or_else(|| days_ago_label(self.day_offset))
+    }
+
+    fn daily_metric(&self) -> CostMetric {
+        self.section_metrics.daily.unwrap_or(self.metric)
+    }
+
+    fn monthly_metric(&self) -> CostMetric {
+        self.section_metrics.monthly.unwrap_or(self.metric)
+    }
+
+    fn forecast_metric(&self) -> CostMetric {
+        self.section_metrics.forecast.unwrap_or(self.metric)
+    }
+
+    /// 扱いを変えるレコードタイプ
+    fn record_types(&self, handling: ChargeHandling) -> Vec<&'static str> {
+        let mut record_types = Vec::new();
+        if handling == ChargeHandling::Exclude && self.exclude_credits {
+            record_types.extend(["Credit", "Refund"]);
+        }
+        if self.tax == handling {
+            record_types.push("Tax");
+        }
+        if self.support == handling {
+            record_types.push("Support");
+        }
+        record_types
+    }
+}
+
+impl Default for CostExplorerConfig {
+    fn default() -> Self {
+        Self {
+            group_by: GroupBy::default(),
+            region_count: None,
+            month_to_date_ranking_count: None,
+            focus_service: None,
+            drill_down_count: None,
+            service_forecast_count: None,
+            movers_count: None,
+            movers_order: MoversOrder::default(),
+            movers_floor: 1.0,
+            filter: None,
+            exclude_credits: false,
+            tax: ChargeHandling::default(),
+            support: ChargeHandling::default(),
+            marketplace: ChargeHandling::default(),
+            metric: CostMetric::default(),
+            section_metrics: SectionMetrics::default(),
+            secondary_metric: None,
+            window: None,
+            day_offset: 2,
+            hourly: None,
+            resource_service: None,
+            anomalies: false,
+            savings_plans_utilization: false,
+            savings_plans_coverage: false,
+            savings_plans_coverage_target: 80.0,
+            reservations: false,
+            reservation_utilization_target: 80.0,
+            expiry_warning_days: None,
+            purchase_recommendations: false,
+            rightsizing_count: None,
+            purchase_option_breakdown: false,
+            data_transfer: false,
+            top_service_usage_types: false,
+            lambda_function_count: None,
+            cloudwatch_usage: false,
+            ai_spotlight: false,
+            tag_compliance_key: None,
+            environment_tag_key: None,
+            compare_previous: false,
+            daily_chart: false,
+            rank_changes: false,
+            compare_last_week: false,
+            compare_last_month: false,
+            detect_new_services: false,
+            detect_disappeared_services: false,
+            month_start_day: 1,
+        }
+    }
+}
+
+/// ランキングの集計単位
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GroupBy {
+    /// サービス別
+    #[default]
+    Service,
+    /// コスト配分タグの値別 (例: `Project`・`Team`)。タグのない料金は 1 行にまとめる
+    Tag { key: String },
+    /// コストカテゴリの値別 (例: チーム・プロダクト)。分類されていない料金は 1 行にまとめる
+    CostCategory { name: String },
+}
+
+impl GroupBy {
+    fn definition(&self) -> GroupDefinition {
+        match self {
+            Self::Service => dimension("SERVICE"),
+            Self::Tag { key } => GroupDefinition::builder().r#type(GroupDefinitionType::Tag).key(key).build(),
+            Self::CostCategory { name } => GroupDefinition::builder().r#type(GroupDefinitionType::CostCategory).key(name).build(),
+        }
+    }
+
+    /// グループのキーを表示名にする。タグとコストカテゴリは `{key}${value}` の形で返る
+    fn display_name(&self, group_key: &str) -> String {
+        let (key, empty) = match self {
+            Self::Service => return group_key.to_string(),
+            Self::Tag { key } => (key, UNTAGGED),
+            Self::CostCategory { name } => (name, UNCATEGORIZED),
+        };
+        match group_key.strip_prefix(key.as_str()).and_then(|rest| rest.strip_prefix('$')) {
+            Some("") => empty.to_string(),
+            Some(value) => value.to_string(),
+            None => group_key.to_string(),
+        }
+    }
+}
+
+/// 今月の予測 (USD)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Forecast {
+    pub mean: f64,
+    pub interval: Option<ForecastInterval>,
+}
+
+/// 集計期間のグループ別の料金
+#[derive(Debug)]
+pub struct CostAndUsage {
+    /// 料金の降順
+    pub groups: Vec<Group>,
+    /// まだ確定していない速報値を含むか
+    pub estimated: bool,
+}
+
+/// Cost Explorer から料金を取得する
+pub struct CostExplorer {
+    client: costexplorer::Client,
+    config: CostExplorerConfig,
+    /// 設定のフィルタ
+    scope: Option<Expression>,
+    /// 設定のフィルタに、除く・別に載せるレコードタイプと AWS Marketplace の除外を加えたもの
+    filter: Option<Expression>,
+    /// 別に載せる AWS Marketplace の料金に絞るフィルタ。別に載せない設定なら `None`
+    marketplace: Option<Expression>,
+}
+
+impl CostExplorer {
+    pub async fn new(config: CostExplorerConfig) -> Self {
+        let sdk_config = aws_config::load_from_env().await;
+        let scope = config.filter.as_ref().map(Filter::to_expression);
+        let mut excluded = config.record_types(ChargeHandling::Exclude);
+        excluded.extend(config.record_types(ChargeHandling::Separate));
+        let charges = if excluded.is_empty() {
+            scope.clone()
+        } else {
+            filter::and(scope.clone(), Some(filter::exclude_record_types(&excluded)))
+        };
+        let filter = match config.marketplace {
+            ChargeHandling::Include => charges.clone(),
+            ChargeHandling::Exclude | ChargeHandling::Separate => filter::and(charges.clone(), Some(filter::exclude_billing_entity(MARKETPLACE))),
+        };
+        let marketplace = (config.marketplace == ChargeHandling::Separate).then(|| filter::and(charges, Some(filter::billing_entity(MARKETPLACE)))).flatten();
+        Self { client: costexplorer::Client::new(&sdk_config), config, scope, filter, marketplace }
+    }
+
+    fn window(&self, today: NaiveDate) -> Window {
+        self.config.window(today)
+    }
+
+    fn period_label(&self) -> String {
+        self.config.period_label()
+    }
+
+    /// 集計期間 (既定は `day_offset` 日前) の利用料金を返す
+    pub async fn fetch_cost_and_usage(&self, today: NaiveDate) -> Result<CostAndUsage, MyError> {
+        self.fetch_daily_groups(today, self.config.group_by.definition(), self.filter.clone()).await
+    }
+
+    /// 集計期間のリージョン別料金を返す。設定されていなければ `None`
+    pub async fn fetch_region_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let Some(region_count) = self.config.region_count else {
+            return Ok(None);
+        };
+        let groups = self.fetch_daily_groups(today, dimension("REGION"), self.filter.clone()).await?.groups;
+        let regions = self.to_costs(&groups, |key| {
+            // グローバルなサービスはリージョンが空で返る
+            if key.is_empty() || key == "NoRegion" { "global".to_string() } else { key.to_string() }
+        });
+        Ok(Some(Breakdown { title: format!("{}のリージョン別料金", self.period_label()), items: roll_up(regions, region_count, OTHER_REGIONS) }))
+    }
+
+    /// 今月の現時点料金のサービス別ランキングを返す。設定されていなければ `None`
+    pub async fn fetch_month_to_date_ranking(&self, today: NaiveDate, aliases: &HashMap<String, String>) -> Result<Option<Breakdown>, MyError> {
+        let Some(count) = self.config.month_to_date_ranking_count else {
+            return Ok(None);
+        };
+        let mut services: Vec<_> = self.fetch_month_to_date_by_service(today, aliases).await?.into_iter()
+            .map(|(name, amount)| ServiceCost::new(name, amount))
+            .collect();
+        services.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+        Ok(Some(Breakdown { title: "今月のサービス別料金".to_string(), items: roll_up(services, count, OTHER_SERVICES) }))
+    }
+
+    /// 注目するサービスの集計期間の利用タイプ別料金を返す。設定されていなければ `None`
+    pub async fn fetch_focus_service_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let Some(service) = &self.config.focus_service else {
+            return Ok(None);
+        };
+        Ok(Some(self.fetch_usage_type_breakdown(today, service, service).await?))
+    }
+
+    /// ランキング上位のサービスそれぞれの利用タイプ別料金を返す。注目するサービスは別に載せるので除く
+    pub async fn fetch_drill_down(&self, today: NaiveDate, cost_and_usages: &[Group], aliases: &HashMap<String, String>) -> Result<Vec<Breakdown>, MyError> {
+        let Some(drill_down_count) = self.config.drill_down_count else {
+            return Ok(Vec::new());
+        };
+        let services = cost_and_usages.iter()
+            .filter_map(|group| group.keys.as_ref()?.first())
+            .filter(|service| self.config.focus_service.as_ref() != Some(*service))
+            .take(drill_down_count);
+        try_join_all(services.map(|service| {
+            self.fetch_usage_type_breakdown(today, service, aliases.get(service).unwrap_or(service))
+        })).await
+    }
+
+    /// 集計期間の EC2 インスタンスの購入オプション別料金を返す。設定されていないか、EC2 の料金がなければ `None`
+    pub async fn fetch_purchase_option_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.purchase_option_breakdown {
+            return Ok(None);
+        }
+        let filter = filter::and(self.filter.clone(), Some(filter::service(EC2_COMPUTE)));
+        let groups = self.fetch_daily_groups(today, dimension("PURCHASE_TYPE"), filter).await?.groups;
+        let options = self.to_costs(&groups, purchase_option_label);
+        let Some(spot) = spot_percent(&options) else {
+            return Ok(None);
+        };
+        Ok(Some(Breakdown { title: format!("{}の EC2 の購入オプション別料金 (スポット比率 {spot:.1}%)", self.period_label()), items: options }))
+    }
+
+    /// 集計期間のデータ転送の料金を種類別に返す。設定されていないか、データ転送の料金がなければ `None`
+    pub async fn fetch_data_transfer_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.data_transfer {
+            return Ok(None);
+        }
+        let groups = self.fetch_daily_groups(today, dimension("USAGE_TYPE"), self.filter.clone()).await?.groups;
+        let transfers = data_transfer_costs(self.to_costs(&groups, str::to_string));
+        if transfers.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Breakdown { title: format!("{}のデータ転送料金", self.period_label()), items: transfers }))
+    }
+
+    /// 集計期間の CloudWatch の料金を、ログの取り込み・保存・API 呼び出しなどの種類別に返す。設定されていないか、料金がなければ `None`
+    pub async fn fetch_cloudwatch_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.cloudwatch_usage {
+            return Ok(None);
+        }
+        let filter = filter::and(self.filter.clone(), Some(filter::service(CLOUDWATCH)));
+        let groups = self.fetch_daily_groups(today, dimension("USAGE_TYPE"), filter).await?.groups;
+        let categories = sum_by_category(self.to_costs(&groups, str::to_string), cloudwatch_category);
+        if categories.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Breakdown { title: format!("{}の CloudWatch の種類別料金", self.period_label()), items: categories }))
+    }
+
+    /// 合計やランキングとは別に載せる AWS Marketplace の集計期間の料金を製品ごとに返す。設定されていないか、料金がなければ `None`
+    pub async fn fetch_marketplace_charges(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let Some(filter) = self.marketplace.clone() else {
+            return Ok(None);
+        };
+        let groups = self.fetch_daily_groups(today, dimension("SERVICE"), Some(filter)).await?.groups;
+        let products = self.to_costs(&groups, str::to_string);
+        if products.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Breakdown { title: format!("{}の AWS Marketplace の料金 (合計・ランキングとは別)", self.period_label()), items: products }))
+    }
+
+    /// 合計やランキングとは別に載せるレコードタイプの集計期間の料金を返す。なければ `None`
+    pub async fn fetch_separated_charges(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let separated = self.config.record_types(ChargeHandling::Separate);
+        if separated.is_empty() {
+            return Ok(None);
+        }
+        let filter = filter::and(self.scope.clone(), Some(filter::record_types(&separated)));
+        let groups = self.fetch_daily_groups(today, dimension("RECORD_TYPE"), filter).await?.groups;
+        let charges = self.to_costs(&groups, record_type_label);
+        Ok(Some(Breakdown { title: format!("{}の別掲の料金", self.period_label()), items: charges }))
+    }
+
+    /// 昨日の料金の高い時間帯を返す。設定されていなければ `None`
+    pub async fn fetch_hourly_breakdown(&self, today: NaiveDate, timezone: Tz) -> Result<Option<Breakdown>, MyError> {
+        let Some(hourly) = self.config.hourly else {
+            return Ok(None);
+        };
+        // 時間単位の問い合わせは日時を UTC の `yyyy-MM-ddThh:mm:ssZ` で指定する
+        let midnight = |date: NaiveDate| {
+            timezone.from_local_datetime(&date.and_time(chrono::NaiveTime::MIN)).earliest()
+                .map(|datetime| datetime.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .ok_or_else(|| format!("{date} の 0 時を {timezone} で表せません"))
+        };
+        let yesterday = today - chrono::Duration::days(1);
+        let metric = self.config.daily_metric().name();
+
+        let request = self.client.get_cost_and_usage()
+            .time_period(DateInterval::builder().start(midnight(yesterday)?).end(midnight(today)?).build()?)
+            .granularity(Granularity::Hourly)
+            .metrics(metric)
+            .set_filter(self.filter.clone());
+        let mut hours = Vec::new();
+        for result in send_all_pages(request).await? {
+            let (Some(period), Some(cost)) = (result.time_period, result.total.and_then(|mut total| total.remove(metric))) else {
+                continue;
+            };
+            check_unit(&cost)?;
+            let start = DateTime::parse_from_rfc3339(&period.start)?.with_timezone(&timezone);
+            hours.push((start.format("%H:00").to_string(), parse_amount(&cost).unwrap_or(0.0)));
+        }
+        Ok(Some(Breakdown { title: "昨日の料金が高い時間帯".to_string(), items: hourly.top_hours(hours) }))
+    }
+
+    /// Cost Anomaly Detection が過去 24 時間に検出した異常を影響額の大きい順に返す。
+    /// 設定されていないか、異常がなければ `None`
+    pub async fn fetch_anomalies(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.anomalies {
+            return Ok(None);
+        }
+        let yesterday = today - chrono::Duration::days(1);
+        let interval = AnomalyDateInterval::builder().start_date(yesterday.to_string()).end_date(today.to_string()).build()?;
+        let mut anomalies = Vec::new();
+        let mut next_page_token = None;
+        loop {
+            let output = self.client.get_anomalies()
+                .date_interval(interval.clone())
+                .set_next_page_token(next_page_token)
+                .send()
+                .await?;
+            anomalies.extend(output.anomalies);
+            next_page_token = output.next_page_token;
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+        if anomalies.is_empty() {
+            return Ok(None);
+        }
+        let mut items: Vec<ServiceCost> = anomalies.iter()
+            .map(|anomaly| {
+                let service = anomaly.dimension_value.clone().unwrap_or_else(|| "(不明)".to_string());
+                let causes = anomaly.root_causes.as_deref().unwrap_or_default().iter()
+                    .map(root_cause_label)
+                    .filter(|label| !label.is_empty())
+                    .collect::<Vec<_>>();
+                let name = if causes.is_empty() { service } else { format!("{service} (原因: {})", causes.join(", ")) };
+                let amount = anomaly.impact.as_ref().map(|impact| impact.total_impact).unwrap_or(0.0);
+                ServiceCost::new(name, amount)
+            })
+            .collect();
+        items.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+        Ok(Some(Breakdown { title: "過去 24 時間に検出された料金の異常".to_string(), items }))
+    }
+
+    /// Savings Plans の今月のコミットメントの利用状況を返す。設定されていないか、Savings Plans がなければ `None`
+    pub async fn fetch_savings_plans_utilization(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.savings_plans_utilization {
+            return Ok(None);
+        }
+        let period = self.config.month_to_date(today)?;
+        let output = match self.client.get_savings_plans_utilization()
+            .time_period(DateInterval::builder().start(period.start.to_string()).end(period.end.to_string()).build()?)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) if e.code() == Some("DataUnavailableException") => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let total = output.total;
+        let utilization = SavingsPlansUtilization {
+            used_commitment: parse_or_zero(total.utilization.used_commitment.as_ref()),
+            unused_commitment: parse_or_zero(total.utilization.unused_commitment.as_ref()),
+            utilization_percent: parse_or_zero(total.utilization.utilization_percentage.as_ref()),
+            net_savings: parse_or_zero(total.savings.as_ref().and_then(|savings| savings.net_savings.as_ref())),
+        };
+        Ok(Some(utilization.to_breakdown(&period.label())))
+    }
+
+    /// Savings Plans の今月のカバー率を返す。設定されていないか、対象の料金がなければ `None`
+    pub async fn fetch_savings_plans_coverage(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.savings_plans_coverage {
+            return Ok(None);
+        }
+        let period = self.config.month_to_date(today)?;
+        let interval = DateInterval::builder().start(period.start.to_string()).end(period.end.to_string()).build()?;
+        let mut coverage = SavingsPlansCoverage { covered: 0.0, on_demand: 0.0 };
+        let mut next_token = None;
+        loop {
+            let output = match self.client.get_savings_plans_coverage()
+                .time_period(interval.clone())
+                .set_next_token(next_token)
+                .send()
+                .await
+            {
+                Ok(output) => output,
+                Err(e) if e.code() == Some("DataUnavailableException") => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            // 期間が暦月をまたぐと月ごとに返るので合計する
+            for data in output.savings_plans_coverages.iter().filter_map(|coverage| coverage.coverage.as_ref()) {
+                coverage.covered += parse_or_zero(data.spend_covered_by_savings_plans.as_ref());
+                coverage.on_demand += parse_or_zero(data.on_demand_cost.as_ref());
+            }
+            next_token = output.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        if coverage.percent().is_none() {
+            return Ok(None);
+        }
+        Ok(Some(coverage.to_breakdown(&period.label(), self.config.savings_plans_coverage_target)))
+    }
+
+    /// リザーブドインスタンスの今月の利用状況を返す。設定されていないか、予約がなければ `None`
+    pub async fn fetch_reservations(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.reservations {
+            return Ok(None);
+        }
+        let period = self.config.month_to_date(today)?;
+        let reservations = self.fetch_reservation_utilizations(period).await?;
+        if reservations.is_empty() {
+            return Ok(None);
+        }
+
+        let interval = DateInterval::builder().start(period.start.to_string()).end(period.end.to_string()).build()?;
+        let coverage = self.client.get_reservation_coverage().time_period(interval).send().await?;
+        let coverage_percent = coverage.total
+            .and_then(|total| total.coverage_hours)
+            .and_then(|hours| hours.coverage_hours_percentage)
+            .and_then(|percent| percent.parse::<f64>().ok());
+        Ok(Some(reservations_breakdown(&period.label(), reservations, coverage_percent, self.config.reservation_utilization_target)))
+    }
+
+    /// 期限が近いリザーブドインスタンスと Savings Plans を、今月の料金から見積もった月額とともに返す。
+    /// 設定されていないか、期限が近いものがなければ `None`
+    pub async fn fetch_expiring_commitments(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let Some(days) = self.config.expiry_warning_days else {
+            return Ok(None);
+        };
+        let period = self.config.month_to_date(today)?;
+        // 期間の料金を 30 日あたりに直す
+        let monthly = |amount: f64| amount / period.days() as f64 * 30.0;
+        let mut commitments: Vec<Expiring> = self.fetch_reservation_utilizations(period).await?
+            .into_iter()
+            .filter_map(|reservation| Some(Expiring { name: format!("RI {}", reservation.name), end_date: reservation.end_date?, monthly_cost: monthly(reservation.amortized_fee) }))
+            .collect();
+
+        let interval = DateInterval::builder().start(period.start.to_string()).end(period.end.to_string()).build()?;
+        let mut next_token = None;
+        loop {
+            let output = match self.client.get_savings_plans_utilization_details()
+                .time_period(interval.clone())
+                .set_next_token(next_token)
+                .send()
+                .await
+            {
+                Ok(output) => output,
+                Err(e) if e.code() == Some("DataUnavailableException") => break,
+                Err(e) => return Err(e.into()),
+            };
+            for detail in output.savings_plans_utilization_details {
+                let attributes = detail.attributes.unwrap_or_default();
+                let Some(end_date) = attributes.get("EndDateTime").and_then(|end| parse_date_prefix(end)) else {
+                    continue;
+                };
+                let id = detail.savings_plan_arn.as_deref().and_then(|arn| arn.rsplit('/').next()).unwrap_or_default();
+                let label = ["SavingsPlansType", "InstanceFamily", "Region"].iter().filter_map(|key| attributes.get(*key)).cloned().collect::<Vec<_>>().join(" ");
+                let amortized = parse_or_zero(detail.amortized_commitment.as_ref().and_then(|commitment| commitment.total_amortized_commitment.as_ref()));
+                commitments.push(Expiring { name: format!("Savings Plans {label} ({id})"), end_date, monthly_cost: monthly(amortized) });
+            }
+            next_token = output.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(expiry_breakdown(today, days, commitments))
+    }
+
+    /// 直近 30 日の利用から、1 年・前払いなしで購入した場合の Compute Savings Plans と予約の推奨を返す。
+    /// 設定されていないか、節約が見込めなければ `None`
+    pub async fn fetch_purchase_recommendations(&self) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.purchase_recommendations {
+            return Ok(None);
+        }
+        let mut recommendations = Vec::new();
+        let savings_plans = self.client.get_savings_plans_purchase_recommendation()
+            .savings_plans_type(SupportedSavingsPlansType::ComputeSp)
+            .term_in_years(TermInYears::OneYear)
+            .payment_option(PaymentOption::NoUpfront)
+            .lookback_period_in_days(LookbackPeriodInDays::ThirtyDays)
+            .send()
+            .await?;
+        if let Some(summary) = savings_plans.savings_plans_purchase_recommendation.and_then(|recommendation| recommendation.savings_plans_purchase_recommendation_summary) {
+            recommendations.push(Recommendation {
+                name: format!("Compute Savings Plans (1 時間あたり ${:.2} のコミットメント)", parse_or_zero(summary.hourly_commitment_to_purchase.as_ref())),
+                monthly_savings: parse_or_zero(summary.estimated_monthly_savings_amount.as_ref()),
+            });
+        }
+        let reservations = try_join_all(RESERVATION_SERVICES.iter().map(|(service, _)| {
+            self.client.get_reservation_purchase_recommendation()
+                .service(*service)
+                .term_in_years(TermInYears::OneYear)
+                .payment_option(PaymentOption::NoUpfront)
+                .lookback_period_in_days(LookbackPeriodInDays::ThirtyDays)
+                .send()
+        })).await?;
+        for ((_, label), output) in RESERVATION_SERVICES.iter().zip(reservations) {
+            for recommendation in output.recommendations.unwrap_or_default() {
+                let count = recommendation.recommendation_details.as_ref().map_or(0, Vec::len);
+                let monthly_savings = recommendation.recommendation_summary.as_ref()
+                    .map_or(0.0, |summary| parse_or_zero(summary.total_estimated_monthly_savings_amount.as_ref()));
+                recommendations.push(Recommendation { name: format!("RI {label} ({count} 件)"), monthly_savings });
+            }
+        }
+        Ok(recommendations_breakdown(recommendations))
+    }
+
+    /// EC2 インスタンスの適正化の推奨を返す。設定されていないか、推奨がなければ `None`
+    pub async fn fetch_rightsizing_recommendations(&self) -> Result<Option<Breakdown>, MyError> {
+        let Some(count) = self.config.rightsizing_count else {
+            return Ok(None);
+        };
+        let mut recommendations = Vec::new();
+        let mut next_page_token = None;
+        loop {
+            let output = self.client.get_rightsizing_recommendation()
+                .service("AmazonEC2")
+                .set_next_page_token(next_page_token)
+                .send()
+                .await?;
+            for recommendation in output.rightsizing_recommendations.unwrap_or_default() {
+                let instance = recommendation.current_instance.as_ref();
+                let id = instance.and_then(|instance| instance.instance_name.clone().filter(|name| !name.is_empty()).or_else(|| instance.resource_id.clone())).unwrap_or_default();
+                let current_type = instance
+                    .and_then(|instance| instance.resource_details.as_ref()?.ec2_resource_details.as_ref()?.instance_type.clone())
+                    .unwrap_or_default();
+                let (action, monthly_savings) = match recommendation.rightsizing_type {
+                    Some(RightsizingType::Terminate) => {
+                        let savings = recommendation.terminate_recommendation_detail.as_ref().and_then(|detail| detail.estimated_monthly_savings.as_ref());
+                        ("停止".to_string(), parse_or_zero(savings))
+                    }
+                    _ => {
+                        // 候補のうち最も節約できるインスタンスタイプを勧める
+                        let target = recommendation.modify_recommendation_detail.as_ref()
+                            .and_then(|detail| detail.target_instances.as_ref())
+                            .and_then(|targets| targets.iter().max_by(|a, b| {
+                                parse_or_zero(a.estimated_monthly_savings.as_ref()).total_cmp(&parse_or_zero(b.estimated_monthly_savings.as_ref()))
+                            }));
+                        let Some(target) = target else {
+                            continue;
+                        };
+                        let target_type = target.resource_details.as_ref()
+                            .and_then(|details| details.ec2_resource_details.as_ref()?.instance_type.clone())
+                            .unwrap_or_default();
+                        (format!("{target_type} に変更"), parse_or_zero(target.estimated_monthly_savings.as_ref()))
+                    }
+                };
+                recommendations.push(Recommendation { name: format!("{id} ({current_type}): {action}"), monthly_savings });
+            }
+            next_page_token = output.next_page_token;
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+        Ok(rightsizing_breakdown(recommendations, count))
+    }
+
+    /// 期間内のリザーブドインスタンスの予約ごとの利用状況を返す。予約がなければ空
+    async fn fetch_reservation_utilizations(&self, period: Window) -> Result<Vec<ReservationUtilization>, MyError> {
+        let interval = DateInterval::builder().start(period.start.to_string()).end(period.end.to_string()).build()?;
+        // 期間が暦月をまたぐと月ごとに返るので、予約ごとに時間数と料金を合計する
+        let mut reservations: Vec<ReservationUtilization> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut next_page_token = None;
+        loop {
+            let output = match self.client.get_reservation_utilization()
+                .time_period(interval.clone())
+                .group_by(dimension("SUBSCRIPTION_ID"))
+                .set_next_page_token(next_page_token)
+                .send()
+                .await
+            {
+                Ok(output) => output,
+                Err(e) if e.code() == Some("DataUnavailableException") => return Ok(Vec::new()),
+                Err(e) => return Err(e.into()),
+            };
+            for group in output.utilizations_by_time.into_iter().flat_map(|utilization| utilization.groups.unwrap_or_default()) {
+                let (Some(id), Some(aggregates)) = (group.value, group.utilization) else {
+                    continue;
+                };
+                let i = *index.entry(id.clone()).or_insert_with(|| {
+                    let attributes = group.attributes.unwrap_or_default();
+                    let label = ["instanceType", "region"].iter().filter_map(|key| attributes.get(*key)).cloned().collect::<Vec<_>>().join(" ");
+                    let name = if label.is_empty() { id } else { format!("{label} ({id})") };
+                    let end_date = attributes.get("endDateTime").and_then(|end| parse_date_prefix(end));
+                    reservations.push(ReservationUtilization { name, end_date, purchased_hours: 0.0, used_hours: 0.0, amortized_fee: 0.0 });
+                    reservations.len() - 1
+                });
+                reservations[i].purchased_hours += parse_or_zero(aggregates.purchased_hours.as_ref());
+                reservations[i].used_hours += parse_or_zero(aggregates.total_actual_hours.as_ref());
+                reservations[i].amortized_fee += parse_or_zero(aggregates.total_amortized_fee.as_ref());
+            }
+            next_page_token = output.next_page_token;
+            if next_page_token.is_none() {
+                return Ok(reservations);
+            }
+        }
+    }
+
+    /// 過去 14 日間で料金の高いリソースを返す。設定されていなければ `None`
+    pub async fn fetch_resource_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let Some(service) = &self.config.resource_service else {
+            return Ok(None);
+        };
+        let mut resources = self.fetch_resource_costs(today, service).await?;
+        resources.truncate(RESOURCE_COUNT);
+        Ok(Some(Breakdown { title: format!("過去{RESOURCE_DAYS}日間の {service} のリソース別料金"), items: resources }))
+    }
+
+    /// 過去 14 日間で料金の高い Lambda 関数を返す。設定されていないか、関数の料金がなければ `None`
+    pub async fn fetch_lambda_function_breakdown(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        let Some(count) = self.config.lambda_function_count else {
+            return Ok(None);
+        };
+        let functions: Vec<_> = self.fetch_resource_costs(today, LAMBDA).await?.into_iter()
+            .take(count)
+            .map(|function| ServiceCost { name: lambda_function_name(&function.name).to_string(), ..function })
+            .collect();
+        if functions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Breakdown { title: format!("過去{RESOURCE_DAYS}日間の Lambda 関数別料金"), items: functions }))
+    }
+
+    /// 集計期間の料金のうち、`tag_compliance_key` のタグのない分を返す。設定されていなければ `None`
+    pub async fn fetch_untagged_cost(&self, today: NaiveDate) -> Result<Option<f64>, MyError> {
+        let Some(key) = &self.config.tag_compliance_key else {
+            return Ok(None);
+        };
+        let by_tag = GroupBy::Tag { key: key.clone() };
+        let groups = self.fetch_daily_groups(today, by_tag.definition(), self.filter.clone()).await?.groups;
+        Ok(Some(self.to_costs(&groups, |group_key| by_tag.display_name(group_key)).into_iter()
+            .filter(|tag| tag.name == UNTAGGED)
+            .map(|tag| tag.amount)
+            .sum()))
+    }
+
+    /// 集計期間の料金を `environment_tag_key` のタグの値別に、高い順で返す。設定されていなければ空
+    pub async fn fetch_environment_costs(&self, today: NaiveDate) -> Result<Vec<ServiceCost>, MyError> {
+        let Some(key) = &self.config.environment_tag_key else {
+            return Ok(Vec::new());
+        };
+        let by_tag = GroupBy::Tag { key: key.clone() };
+        let groups = self.fetch_daily_groups(today, by_tag.definition(), self.filter.clone()).await?.groups;
+        Ok(self.to_costs(&groups, |group_key| by_tag.display_name(group_key)))
+    }
+
+    /// Bedrock・SageMaker・GPU インスタンスの今月の現時点料金と、先月の同じ日数の料金を返す。設定されていなければ `None`
+    pub async fn fetch_ai_spotlight(&self, today: NaiveDate) -> Result<Option<Spotlight>, MyError> {
+        if !self.config.ai_spotlight {
+            return Ok(None);
+        }
+        let current = self.config.month_to_date(today)?;
+        let last_month = self.config.last_month(current.start)?;
+        let previous = Window { end: (last_month.start + (current.end - current.start)).min(last_month.end), ..last_month };
+        Ok(Some(Spotlight { amount: self.fetch_ai_cost_in(current).await?, previous: self.fetch_ai_cost_in(previous).await? }))
+    }
+
+    /// 期間中の AI サービスと GPU インスタンスの料金の合計
+    async fn fetch_ai_cost_in(&self, window: Window) -> Result<f64, MyError> {
+        let services = self.fetch_groups_in(window, dimension("SERVICE"), self.filter.clone()).await?.groups;
+        let filter = filter::and(self.filter.clone(), Some(filter::service(EC2_COMPUTE)));
+        let usage_types = self.fetch_groups_in(window, dimension("USAGE_TYPE"), filter).await?.groups;
+        let services = self.to_costs(&services, str::to_string).into_iter().filter(|service| is_ai_service(&service.name));
+        let instances = self.to_costs(&usage_types, str::to_string).into_iter().filter(|usage_type| is_accelerated_usage_type(&usage_type.name));
+        Ok(services.chain(instances).map(|cost| cost.amount).sum())
+    }
+
+    /// 過去 14 日間のサービスのリソース別料金を高い順に返す。Cost Explorer でリソース単位のデータを有効にしておく必要がある
+    async fn fetch_resource_costs(&self, today: NaiveDate, service: &str) -> Result<Vec<ServiceCost>, MyError> {
+        let start = today - chrono::Duration::days(RESOURCE_DAYS);
+        let metric = self.config.daily_metric().name();
+        let request = self.client.get_cost_and_usage_with_resources()
+            .time_period(DateInterval::builder().start(start.to_string()).end(today.to_string()).build()?)
+            .granularity(Granularity::Daily)
+            .metrics(metric)
+            .group_by(dimension("RESOURCE_ID"))
+            .set_filter(filter::and(self.filter.clone(), Some(filter::service(service))));
+
+        let mut results = Vec::new();
+        let mut next_page_token = None;
+        loop {
+            let output = request.clone().set_next_page_token(next_page_token).send().await?;
+            results.extend(output.results_by_time.unwrap_or_default());
+            next_page_token = output.next_page_token;
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+        let groups = aggregate(results);
+        check_units(&groups, metric)?;
+        let mut resources: Vec<ServiceCost> = groups.iter()
+            .filter_map(|group| {
+                let name = group.keys.as_ref()?.first()?.clone();
+                let amount = group.metrics.as_ref()?.get(metric).and_then(parse_amount)?;
+                Some(ServiceCost::new(name, amount))
+            })
+            .collect();
+        resources.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+        Ok(resources)
+    }
+
+    /// 集計期間のサービスの利用タイプ別料金のうち、高い順に `count` 件を返す
+    pub async fn fetch_top_usage_types(&self, today: NaiveDate, service: &str, count: usize) -> Result<Vec<ServiceCost>, MyError> {
+        let groups = self.fetch_daily_groups(today, dimension("USAGE_TYPE"), filter::and(self.filter.clone(), Some(filter::service(service)))).await?.groups;
+        let mut usage_types = self.to_costs(&groups, str::to_string);
+        usage_types.truncate(count);
+        Ok(usage_types)
+    }
+
+    async fn fetch_usage_type_breakdown(&self, today: NaiveDate, service: &str, display_name: &str) -> Result<Breakdown, MyError> {
+        let groups = self.fetch_daily_groups(today, dimension("USAGE_TYPE"), filter::and(self.filter.clone(), Some(filter::service(service)))).await?.groups;
+        let usage_types = self.to_costs(&groups, str::to_string);
+        Ok(Breakdown { title: format!("{}の {display_name} の利用タイプ別料金", self.period_label()), items: roll_up(usage_types, USAGE_TYPE_COUNT, "その他") })
+    }
+
+    /// 集計期間の料金を指定した単位で集計し、降順で返す
+    async fn fetch_daily_groups(&self, today: NaiveDate, group_by: GroupDefinition, filter: Option<Expression>) -> Result<CostAndUsage, MyError> {
+        self.fetch_groups_in(self.window(today), group_by, filter).await
+    }
+
+    /// 集計期間を `days` 日前にずらした期間の料金を、ランキングと同じ表示名をキーにして返す
+    pub async fn fetch_shifted_costs(&self, today: NaiveDate, days: i64, aliases: &HashMap<String, String>) -> Result<HashMap<String, f64>, MyError> {
+        self.fetch_costs_in(self.window(today).shifted(days), aliases).await
+    }
+
+    /// 集計期間の直前の同じ長さの期間の料金を、ランキングと同じ表示名をキーにして返す
+    pub async fn fetch_previous_costs(&self, today: NaiveDate, aliases: &HashMap<String, String>) -> Result<HashMap<String, f64>, MyError> {
+        self.fetch_costs_in(self.window(today).previous(), aliases).await
+    }
+
+    async fn fetch_costs_in(&self, window: Window, aliases: &HashMap<String, String>) -> Result<HashMap<String, f64>, MyError> {
+        let groups = self.fetch_groups_in(window, self.config.group_by.definition(), self.filter.clone()).await?.groups;
+        Ok(self.to_service_costs(&groups, aliases).into_iter().map(|service| (service.name, service.amount)).collect())
+    }
+
+    /// 集計期間の直前 `days` 日間の料金を、ランキングと同じ表示名をキーにして返す
+    pub async fn fetch_preceding_costs(&self, today: NaiveDate, days: u32, aliases: &HashMap<String, String>) -> Result<HashMap<String, f64>, MyError> {
+        let end = self.window(today).start;
+        let window = Window { start: end - chrono::Duration::days(days.into()), end, granularity: WindowGranularity::Monthly };
+        let groups = self.fetch_groups_in(window, self.config.group_by.definition(), self.filter.clone()).await?.groups;
+        Ok(self.to_service_costs(&groups, aliases).into_iter().map(|service| (service.name, service.amount)).collect())
+    }
+
+    async fn fetch_groups_in(&self, window: Window, group_by: GroupDefinition, filter: Option<Expression>) -> Result<CostAndUsage, MyError> {
+        let mut metrics = vec![self.config.daily_metric().name().to_string()];
+        metrics.extend(self.config.secondary_metric.map(|secondary| secondary.name().to_string()));
+
+        let request = self.client.get_cost_and_usage()
+            .time_period(DateInterval::builder().start(window.start.to_string()).end(window.end.to_string()).build()?)
+            .granularity(window.granularity.to_granularity())
+            .set_metrics(Some(metrics))
+            .group_by(group_by)
+            .set_filter(filter);
+        let results = send_all_pages(request).await?;
+        if results.iter().all(|result| result.groups.is_none()) {
+            return Err("No groups found in the results".into());
+        }
+        let estimated = results.iter().any(|result| result.estimated);
+        let mut groups = aggregate(results);
+        check_units(&groups, self.config.daily_metric().name())?;
+        if let Some(secondary) = self.config.secondary_metric.filter(|secondary| secondary.is_cost()) {
+            check_units(&groups, secondary.name())?;
+        }
+        groups.sort_by(|a, b| {
+            let a_cost = get_cost(a, self.config.daily_metric());
+            let b_cost = get_cost(b, self.config.daily_metric());
+            b_cost.total_cmp(&a_cost)
+        });
+        Ok(CostAndUsage { groups, estimated })
+    }
+
+    /// グループ別の料金に変換する。別名が設定されているグループは別名で表示する
+    pub fn to_service_costs(&self, cost_and_usages: &[Group], aliases: &HashMap<String, String>) -> Vec<ServiceCost> {
+        self.to_costs(cost_and_usages, |key| {
+            let name = self.config.group_by.display_name(key);
+            aliases.get(&name).cloned().unwrap_or(name)
+        })
+    }
+
+    /// グループのキーを表示名にして料金に変換する
+    fn to_costs(&self, groups: &[Group], name: impl Fn(&str) -> String) -> Vec<ServiceCost> {
+        groups.iter()
+            .filter_map(|group| {
+                let name = name(group.keys.as_ref()?.first()?);
+                let metrics = group.metrics.as_ref()?;
+                let amount = metrics.get(self.config.daily_metric().name()).and_then(parse_amount)?;
+                let secondary = self.config.secondary_metric.and_then(|secondary| {
+                    let value = metrics.get(secondary.name()).and_then(parse_amount)?;
+                    Some(match secondary {
+                        SecondaryMetric::UsageQuantity => Secondary::Usage { quantity: value },
+                        SecondaryMetric::Cost(metric) => Secondary::Cost { label: metric.label(), amount: value },
+                    })
+                });
+                Some(ServiceCost { secondary, ..ServiceCost::new(name, amount) })
+            })
+            .collect()
+    }
+
+    /// 今月の予測と、その予測区間を返す
+    pub async fn fetch_current_month_cost_forecast(&self, today: NaiveDate) -> Result<Forecast, MyError> {
+        let next_month_1st = self.config.current_month(today)?.end;
+        // Cost Explorer は UTC の今日より前の日付を予測の開始日にできない
+        let start = today.max(chrono::Utc::now().date_naive());
+        let result = self.client.get_cost_forecast().time_period(DateInterval::builder().start(start.to_string()).end(next_month_1st.to_string()).build()?).metric(self.config.forecast_metric().as_forecast_metric()).granularity(Granularity::Monthly).prediction_interval_level(PREDICTION_INTERVAL_LEVEL).set_filter(self.filter.clone()).send().await?;
+        let total = result.total.ok_or_else(|| "Failed to parse the forecasted cost".to_string())?;
+        check_unit(&total)?;
+        let mean = parse_amount(&total).ok_or_else(|| "Failed to parse the forecasted cost".to_string())?;
+        // 月単位で月末までを予測するので、期間は 1 つだけ返る
+        let interval = result.forecast_results_by_time.unwrap_or_default().first().and_then(|result| {
+            Some(ForecastInterval {
+                lower: result.prediction_interval_lower_bound.as_deref()?.parse().ok()?,
+                upper: result.prediction_interval_upper_bound.as_deref()?.parse().ok()?,
+            })
+        });
+        Ok(Forecast { mean, interval })
+    }
+
+    /// 今四半期の合計の予測を返す
+    pub async fn fetch_quarter_forecast(&self, today: NaiveDate) -> Result<f64, MyError> {
+        let start = quarter_start(today).ok_or("Failed to calculate the first day of this quarter")?;
+        let end = start.checked_add_months(Months::new(3)).ok_or("Failed to calculate the first day of next quarter")?;
+        self.fetch_period_forecast(today, start, end).await
+    }
+
+    /// 今年の合計の予測を返す
+    pub async fn fetch_year_forecast(&self, today: NaiveDate) -> Result<f64, MyError> {
+        let start = NaiveDate::from_ymd_opt(today.year(), 1, 1).ok_or("Failed to calculate the first day of this year")?;
+        let end = start.checked_add_months(Months::new(12)).ok_or("Failed to calculate the first day of next year")?;
+        self.fetch_period_forecast(today, start, end).await
+    }
+
+    /// 期間 [start, end) の合計の予測。予測は今日からの分だけ返るので、期間の初めから昨日までの実績を足す
+    async fn fetch_period_forecast(&self, today: NaiveDate, start: NaiveDate, end: NaiveDate) -> Result<f64, MyError> {
+        self.fetch_filtered_forecast(today, start, end, self.filter.clone()).await
+    }
+
+    async fn fetch_filtered_forecast(&self, today: NaiveDate, start: NaiveDate, end: NaiveDate, filter: Option<Expression>) -> Result<f64, MyError> {
+        let forecast_start = today.max(chrono::Utc::now().date_naive());
+        let result = self.client.get_cost_forecast()
+            .time_period(DateInterval::builder().start(forecast_start.to_string()).end(end.to_string()).build()?)
+            .metric(self.config.forecast_metric().as_forecast_metric())
+            .granularity(Granularity::Monthly)
+            .set_filter(filter.clone())
+            .send()
+            .await?;
+        let total = result.total.ok_or_else(|| format!("Failed to parse the forecasted cost until {end}"))?;
+        check_unit(&total)?;
+        let forecast = parse_amount(&total).ok_or_else(|| format!("Failed to parse the forecasted cost until {end}"))?;
+        let actual = if forecast_start > start { self.fetch_total(start, forecast_start, self.config.monthly_metric(), filter).await? } else { 0.0 };
+        Ok(actual + forecast)
+    }
+
+    /// ランキング上位のサービスそれぞれの今月末の料金の予測を、表示名をキーにして返す
+    pub async fn fetch_service_forecasts(&self, today: NaiveDate, cost_and_usages: &[Group], aliases: &HashMap<String, String>) -> Result<HashMap<String, f64>, MyError> {
+        let Some(service_forecast_count) = self.config.service_forecast_count else {
+            return Ok(HashMap::new());
+        };
+        let Window { start, end, .. } = self.config.current_month(today)?;
+        let services: Vec<&String> = cost_and_usages.iter()
+            .filter_map(|group| group.keys.as_ref()?.first())
+            .take(service_forecast_count)
+            .collect();
+        let forecasts = try_join_all(services.iter().map(|service| {
+            self.fetch_filtered_forecast(today, start, end, filter::and(self.filter.clone(), Some(filter::service(service))))
+        })).await?;
+        Ok(services.into_iter()
+            .map(|service| {
+                let name = self.config.group_by.display_name(service);
+                aliases.get(&name).cloned().unwrap_or(name)
+            })
+            .zip(forecasts)
+            .collect())
+    }
+
+    pub async fn fetch_current_month_cost(&self, today: NaiveDate) -> Result<f64, MyError> {
+        let current_month = self.config.current_month(today)?;
+        self.fetch_monthly_total(current_month.start, current_month.end).await
+    }
+
+    /// 前年の同じ月の初日から、前年の今日の前日までの料金を返す
+    pub async fn fetch_last_year_month_to_date(&self, today: NaiveDate) -> Result<f64, MyError> {
+        let end = today.checked_sub_months(Months::new(12)).ok_or("Failed to calculate the same day last year")?;
+        let start = self.config.current_month(end)?.start;
+        if start == end {
+            return Ok(0.0);
+        }
+        self.fetch_total(start, end, self.config.monthly_metric(), self.filter.clone()).await
+    }
+
+    /// 先月の合計料金を返す
+    pub async fn fetch_last_month_cost(&self, today: NaiveDate) -> Result<f64, MyError> {
+        let last_month = self.config.last_month(today)?;
+        self.fetch_monthly_total(last_month.start, last_month.end).await
+    }
+
+    /// 今月の現時点料金をレコードタイプ (利用・税金・クレジットなど) ごとに分けて返す
+    pub async fn fetch_month_to_date_composition(&self, today: NaiveDate) -> Result<Vec<ServiceCost>, MyError> {
+        let metric = self.config.monthly_metric().name();
+        let groups = self.fetch_month_to_date_groups(today, dimension("RECORD_TYPE")).await?;
+        let mut composition: Vec<ServiceCost> = groups.iter()
+            .filter_map(|group| {
+                let name = record_type_label(group.keys.as_ref()?.first()?);
+                let amount = group.metrics.as_ref()?.get(metric).and_then(parse_amount)?;
+                Some(ServiceCost::new(name, amount))
+            })
+            .collect();
+        // クレジットなどの負の料金も大きさの順に並べる
+        composition.sort_by(|a, b| b.amount.abs().total_cmp(&a.amount.abs()));
+        Ok(composition)
+    }
+
+    /// 今月の現時点料金をサービスごとに返す。キーは別名を適用した表示名
+    pub async fn fetch_month_to_date_by_service(&self, today: NaiveDate, aliases: &HashMap<String, String>) -> Result<HashMap<String, f64>, MyError> {
+        let metric = self.config.monthly_metric().name();
+        let groups = self.fetch_month_to_date_groups(today, dimension("SERVICE")).await?;
+        Ok(groups.iter()
+            .filter_map(|group| {
+                let service = group.keys.as_ref()?.first()?;
+                let amount = group.metrics.as_ref()?.get(metric).and_then(parse_amount)?;
+                Some((aliases.get(service).cloned().unwrap_or_else(|| service.clone()), amount))
+            })
+            .collect())
+    }
+
+    async fn fetch_month_to_date_groups(&self, today: NaiveDate, group_by: GroupDefinition) -> Result<Vec<Group>, MyError> {
+        let current_month = self.config.current_month(today)?;
+        let metric = self.config.monthly_metric().name();
+
+        let request = self.client.get_cost_and_usage()
+            .time_period(DateInterval::builder().start(current_month.start.to_string()).end(current_month.end.to_string()).build()?)
+            .granularity(Granularity::Monthly)
+            .metrics(metric)
+            .group_by(group_by)
+            .set_filter(self.filter.clone());
+        let groups = aggregate(send_all_pages(request).await?);
+        check_units(&groups, metric)?;
+        Ok(groups)
+    }
+
+    /// 月単位の期間 [start, end) の合計料金を返す
+    async fn fetch_monthly_total(&self, start: NaiveDate, end: NaiveDate) -> Result<f64, MyError> {
+        self.fetch_total(start, end, self.config.monthly_metric(), self.filter.clone()).await
+    }
+
+    /// 集計期間の最終日までの `days` 日間の 1 日あたりの平均料金を返す
+    pub async fn fetch_moving_average(&self, today: NaiveDate, days: u32) -> Result<f64, MyError> {
+        let end = self.window(today).end;
+        let start = end - chrono::Duration::days(days.into());
+        Ok(self.fetch_total(start, end, self.config.daily_metric(), self.filter.clone()).await? / f64::from(days))
+    }
+
+    /// 集計期間の直前 `days` 日間の日ごとの合計料金を古い順に返す
+    pub async fn fetch_daily_history(&self, today: NaiveDate, days: u32) -> Result<Vec<f64>, MyError> {
+        let end = self.window(today).start;
+        let start = end - chrono::Duration::days(days.into());
+        Ok(self.fetch_daily_totals(start, end).await?.into_iter().map(|(_, cost)| cost).collect())
+    }
+
+    /// 集計期間の日ごとの料金を棒グラフにした内訳を返す。設定されていなければ `None`
+    pub async fn fetch_daily_chart(&self, today: NaiveDate) -> Result<Option<Breakdown>, MyError> {
+        if !self.config.daily_chart {
+            return Ok(None);
+        }
+        let window = self.window(today);
+        let totals = self.fetch_daily_totals(window.start, window.end).await?;
+        let max = totals.iter().map(|(_, cost)| *cost).fold(0.0, f64::max);
+        let items = totals.into_iter()
+            .map(|(date, cost)| ServiceCost::new(format!("{} {}", format_day(date), bar(cost, max)), cost))
+            .collect();
+        Ok(Some(Breakdown { title: format!("{}の日別料金", self.period_label()), items }))
+    }
+
+    /// 期間 [start, end) の日ごとの合計料金を古い順に返す
+    async fn fetch_daily_totals(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<(NaiveDate, f64)>, MyError> {
+        let metric = self.config.daily_metric();
+        let result = self.client.get_cost_and_usage()
+            .time_period(DateInterval::builder().start(start.to_string()).end(end.to_string()).build()?)
+            .granularity(Granularity::Daily)
+            .metrics(metric.name())
+            .set_filter(self.filter.clone())
+            .send()
+            .await?;
+        result.results_by_time.unwrap_or_default().into_iter()
+            .map(|result_by_time| -> Result<(NaiveDate, f64), MyError> {
+                let date = result_by_time.time_period.as_ref()
+                    .and_then(|period| period.start.parse().ok())
+                    .ok_or_else(|| format!("Failed to extract the date of the daily cost from {start} to {end}"))?;
+                let cost = result_by_time.total
+                    .and_then(|total| total.get(metric.name()).cloned())
+                    .ok_or_else(|| format!("Failed to extract the daily cost from {start} to {end}"))?;
+                check_unit(&cost)?;
+                let cost = parse_amount(&cost).ok_or_else(|| format!("Failed to extract the daily cost from {start} to {end}"))?;
+                Ok((date, cost))
+            })
+            .collect()
+    }
+
+    /// 集計期間の直前 `days` 日間の 1 日あたりの平均料金を返す
+    pub async fn fetch_recent_average(&self, today: NaiveDate, days: u32) -> Result<f64, MyError> {
+        let end = self.window(today).start;
+        let start = end - chrono::Duration::days(days.into());
+        Ok(self.fetch_total(start, end, self.config.daily_metric(), self.filter.clone()).await? / f64::from(days))
+    }
+
+    async fn fetch_total(&self, start: NaiveDate, end: NaiveDate, metric: CostMetric, filter: Option<Expression>) -> Result<f64, MyError> {
+        let result = self.client.get_cost_and_usage()
+            .time_period(
+                DateInterval::builder()
+                    .start(start.to_string())
+                    .end(end.to_string())
+                    .build()?
+            )
+            .granularity(Granularity::Monthly)
+            .metrics(metric.name())
+            .set_filter(filter)
+            .send()
+            .await?;
+
+        // 複数の月にわたる期間は月ごとに返るので合計する
+        let mut total_cost = 0.0;
+        for result_by_time in result.results_by_time.unwrap_or_default() {
+            let cost = result_by_time.total
+                .and_then(|total| total.get(metric.name()).cloned())
+                .ok_or_else(|| format!("Failed to extract the cost amount from {start} to {end}"))?;
+            check_unit(&cost)?;
+            total_cost += parse_amount(&cost).ok_or_else(|| format!("Failed to extract the cost amount from {start} to {end}"))?;
+        }
+
+        Ok(total_cost)
+    }
+}
+
+/// `next_page_token` がなくなるまで問い合わせ、全ページの期間ごとの結果を返す。
+/// グループが多いと 1 回の応答に収まらず、残りは次のページで返る
+async fn send_all_pages(request: GetCostAndUsageFluentBuilder) -> Result<Vec<ResultByTime>, MyError> {
+    let mut results = Vec::new();
+    let mut next_page_token = None;
+    loop {
+        let output = request.clone().set_next_page_token(next_page_token).send().await?;
+        results.extend(output.results_by_time.unwrap_or_default());
+        next_page_token = output.next_page_token;
+        if next_page_token.is_none() {
+            return Ok(results);
+        }
+    }
+}
+
+/// Cost Explorer を使えない (権限がない・アカウントで有効になっていない) ときのエラーか
+pub fn is_unavailable(e: &MyError) -> bool {
+    let code = e.downcast_ref::<SdkError<GetCostAndUsageError>>().and_then(|e| e.code())
+        .or_else(|| e.downcast_ref::<SdkError<GetCostForecastError>>().and_then(|e| e.code()));
+    matches!(code, Some("AccessDeniedException" | "DataUnavailableException"))
+}
+
+/// 期間ごと・ページごとの結果のグループを、キーが同じもの同士で指標ごとに合計する。
+/// 期間が複数日にわたると、同じサービスが日ごとの結果に分かれて返る
+fn aggregate(results: Vec<ResultByTime>) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+    for group in results.into_iter().flat_map(|result| result.groups.unwrap_or_default()) {
+        let keys = group.keys.clone().unwrap_or_default();
+        let Some(&i) = index.get(&keys) else {
+            index.insert(keys, groups.len());
+            groups.push(group);
+            continue;
+        };
+        let merged = groups[i].metrics.get_or_insert_with(HashMap::new);
+        for (name, metric) in group.metrics.unwrap_or_default() {
+            let total = merged.get(&name).and_then(parse_amount).unwrap_or(0.0) + parse_amount(&metric).unwrap_or(0.0);
+            let unit = metric.unit.clone();
+            merged.insert(name, MetricValue::builder().amount(total.to_string()).set_unit(unit).build());
+        }
+    }
+    groups
+}
+
+/// `date` を含む四半期 (1〜3 月、4〜6 月、…) の初日
+fn quarter_start(date: NaiveDate) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1)
+}
+
+fn dimension(key: &str) -> GroupDefinition {
+    GroupDefinition::builder().r#type(GroupDefinitionType::Dimension).key(key).build()
+}
+
+/// 異常の原因を `リージョン / 利用タイプ / アカウント` の形にする
+fn root_cause_label(root_cause: &RootCause) -> String {
+    [&root_cause.region, &root_cause.usage_type, &root_cause.linked_account_name]
+        .into_iter()
+        .flatten()
+        .filter(|value| !value.is_empty())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// レコードタイプの表示名
+fn record_type_label(record_type: &str) -> String {
+    match record_type {
+        "Usage" => "利用",
+        "Tax" => "税金",
+        "Credit" => "クレジット",
+        "Refund" => "返金",
+        "Support" => "サポート",
+        other => other,
+    }.to_string()
+}
+
+fn purchase_option_label(purchase_type: &str) -> String {
+    match purchase_type {
+        "On Demand Instances" => "オンデマンド",
+        "Spot Instances" => SPOT,
+        "Standard Reserved Instances" => "リザーブド",
+        "Convertible Reserved Instances" => "コンバーティブルリザーブド",
+        "Savings Plans" => "Savings Plans",
+        other => other,
+    }.to_string()
+}
+
+/// Lambda のリソース ID (`arn:aws:lambda:ap-northeast-1:123456789012:function:name`) から関数名を取り出す
+fn lambda_function_name(resource_id: &str) -> &str {
+    resource_id.split_once(":function:").map_or(resource_id, |(_, name)| name)
+}
+
+/// Bedrock や SageMaker のサービスか
+fn is_ai_service(service: &str) -> bool {
+    AI_SERVICE_KEYWORDS.iter().any(|keyword| service.contains(keyword))
+}
+
+/// EC2 の利用タイプ (例: `APN1-BoxUsage:g5.xlarge`) が GPU や機械学習用チップを載せたインスタンスの利用か
+fn is_accelerated_usage_type(usage_type: &str) -> bool {
+    let Some((usage, instance_type)) = usage_type.split_once(':') else {
+        return false;
+    };
+    if !["BoxUsage", "SpotUsage", "DedicatedUsage"].iter().any(|kind| usage.ends_with(kind)) {
+        return false;
+    }
+    let family: String = instance_type.chars().take_while(|c| c.is_ascii_lowercase()).collect();
+    ACCELERATED_FAMILIES.contains(&family.as_str())
+}
+
+/// 利用タイプ (例: `APN1-DataTransfer-Out-Bytes`) がデータ転送なら、その種類の表示名
+fn data_transfer_category(usage_type: &str) -> Option<&'static str> {
+    if usage_type.contains("NatGateway-Bytes") {
+        Some("NAT ゲートウェイのデータ処理")
+    } else if usage_type.contains("DataTransfer-Regional-Bytes") {
+        Some("AZ 間の転送")
+    } else if usage_type.contains("AWS-Out-Bytes") || usage_type.contains("AWS-In-Bytes") {
+        Some("リージョン間の転送")
+    } else if usage_type.contains("DataTransfer-Out-Bytes") || usage_type.contains("CloudFront-Out-Bytes") {
+        Some("インターネットへの転送")
+    } else {
+        None
+    }
+}
+
+/// CloudWatch の利用タイプ (例: `APN1-DataProcessing-Bytes`) の種類の表示名
+fn cloudwatch_category(usage_type: &str) -> Option<&'static str> {
+    Some(if usage_type.contains("DataProcessing-Bytes") || usage_type.contains("VendedLog-Bytes") {
+        "ログの取り込み"
+    } else if usage_type.contains("TimedStorage-ByteHrs") {
+        "ログの保存"
+    } else if usage_type.contains("Requests") || usage_type.contains("GMD-Metrics") {
+        "API 呼び出し"
+    } else if usage_type.contains("MetricMonitorUsage") {
+        "メトリクス"
+    } else if usage_type.contains("AlarmMonitorUsage") {
+        "アラーム"
+    } else {
+        "その他"
+    })
+}
+
+/// 利用タイプ別の料金からデータ転送の分を種類ごとに合計し、料金の高い順に返す
+fn data_transfer_costs(usage_types: Vec<ServiceCost>) -> Vec<ServiceCost> {
+    sum_by_category(usage_types, data_transfer_category)
+}
+
+/// 利用タイプ別の料金を `category` の種類ごとに合計し、料金の高い順に返す。種類のない利用タイプは除く
+fn sum_by_category(usage_types: Vec<ServiceCost>, category: impl Fn(&str) -> Option<&'static str>) -> Vec<ServiceCost> {
+    let mut categories: Vec<ServiceCost> = Vec::new();
+    for usage_type in usage_types {
+        let Some(name) = category(&usage_type.name) else {
+            continue;
+        };
+        match categories.iter_mut().find(|category| category.name == name) {
+            Some(category) => category.amount += usage_type.amount,
+            None => categories.push(ServiceCost::new(name.to_string(), usage_type.amount)),
+        }
+    }
+    categories.retain(|category| category.amount > 0.0);
+    categories.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    categories
+}
+
+/// 購入オプション別の料金のうちスポットの割合 (%)。料金がなければ `None`
+fn spot_percent(options: &[ServiceCost]) -> Option<f64> {
+    let total: f64 = options.iter().map(|option| option.amount).sum();
+    let spot: f64 = options.iter().filter(|option| option.name == SPOT).map(|option| option.amount).sum();
+    (total > 0.0).then(|| spot / total * 100.0)
+}
+
+fn get_cost(group: &Group, metric: CostMetric) -> f64 {
+    group.metrics.as_ref().and_then(|metrics| metrics.get(metric.name())).and_then(|cost| cost.amount.as_ref()).and_then(|amount| amount.parse::<f64>().ok()).unwrap_or(0.0)
+}
+
+fn parse_amount(metric: &MetricValue) -> Option<f64> {
+    metric.amount.as_ref().and_then(|amount| amount.parse::<f64>().ok())
+}
+
+/// 文字列で返る数値を読む。読めなければ 0
+fn parse_or_zero(value: Option<&String>) -> f64 {
+    value.and_then(|value| value.parse::<f64>().ok()).unwrap_or(0.0)
+}
+
+/// `2027-01-01T00:00:00.000Z` のような日時の日付の部分を読む
+fn parse_date_prefix(datetime: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(datetime.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// 料金の通貨単位が USD であることを確かめる。
+/// 表示通貨への換算レートは 1 USD あたりの額なので、ほかの通貨の金額を誤って換算しないよう、
+/// USD 以外の単位が返ったときはレポート全体をエラーにする。単位が付いていない値は USD とみなす
+fn check_unit(metric: &MetricValue) -> Result<(), MyError> {
+    match metric.unit.as_deref() {
+        None | Some(COST_UNIT) => Ok(()),
+        Some(unit) => Err(format!(
+            "Cost Explorer が {unit} で料金を返しました。為替レートは {COST_UNIT} からの換算のため {COST_UNIT} 以外の通貨単位には対応していません (金額: {})",
+            metric.amount.as_deref().unwrap_or("-"),
+        ).into()),
+    }
+}
+
+/// グループの指標 `metric` の通貨単位をすべて確かめる
+fn check_units(groups: &[Group], metric: &str) -> Result<(), MyError> {
+    groups.iter()
+        .filter_map(|group| group.metrics.as_ref()?.get(metric))
+        .try_for_each(check_unit)
+}
+
+/// 日別のグラフの日付 (例: `10/09(金)`)
+fn format_day(date: NaiveDate) -> String {
+    const WEEKDAYS: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+    format!("{}({})", date.format("%m/%d"), WEEKDAYS[date.weekday().num_days_from_monday() as usize])
+}
+
+/// 最大値を `CHART_WIDTH` 文字とした棒
+fn bar(value: f64, max: f64) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+    "█".repeat((value / max * CHART_WIDTH as f64).round() as usize)
+}
+
+/// 降順の料金のうち上位 `count` 件を残し、残りを `other` の 1 行にまとめる
+fn roll_up(mut items: Vec<ServiceCost>, count: usize, other: &str) -> Vec<ServiceCost> {
+    if items.len() <= count {
+        return items;
+    }
+    let amount = items.split_off(count).iter().map(|item| item.amount).sum();
+    items.push(ServiceCost::new(other.to_string(), amount));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn cost_explorer() -> CostExplorer {
+        CostExplorer::new(CostExplorerConfig::default()).await
+    }
+
+    #[test]
+    fn test_daily_chart_parts() {
+        assert_eq!(format_day(NaiveDate::from_ymd_opt(2026, 10, 9).unwrap()), "10/09(金)");
+        assert_eq!(bar(10.0, 20.0), "█".repeat(10));
+        assert_eq!(bar(0.0, 20.0), "");
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(GroupBy::Service.display_name("AWS Lambda"), "AWS Lambda");
+        let by_project = GroupBy::Tag { key: "Project".to_string() };
+        assert_eq!(by_project.display_name("Project$billing"), "billing");
+        assert_eq!(by_project.display_name("Project$"), UNTAGGED);
+        let by_team = GroupBy::CostCategory { name: "Team".to_string() };
+        assert_eq!(by_team.display_name("Team$platform"), "platform");
+        assert_eq!(by_team.display_name("Team$"), UNCATEGORIZED);
+    }
+
+    #[test]
+    fn test_record_types() {
+        let config = CostExplorerConfig {
+            exclude_credits: true,
+            tax: ChargeHandling::Separate,
+            support: ChargeHandling::Separate,
+            ..CostExplorerConfig::default()
+        };
+        assert_eq!(config.record_types(ChargeHandling::Exclude), ["Credit", "Refund"]);
+        assert_eq!(config.record_types(ChargeHandling::Separate), ["Tax", "Support"]);
+        assert_eq!("Exclude".parse::<ChargeHandling>().unwrap(), ChargeHandling::Exclude);
+    }
+
+    #[test]
+    fn test_section_metrics() {
+        let config = CostExplorerConfig {
+            metric: CostMetric::Amortized,
+            section_metrics: SectionMetrics { forecast: Some(CostMetric::NetUnblended), ..SectionMetrics::default() },
+            ..CostExplorerConfig::default()
+        };
+        assert_eq!(config.daily_metric(), CostMetric::Amortized);
+        assert_eq!(config.forecast_metric().as_forecast_metric(), Metric::NetUnblendedCost);
+        assert!("net_unblended".parse::<CostMetric>().is_ok());
+        assert_eq!("usage_quantity".parse::<SecondaryMetric>().unwrap(), SecondaryMetric::UsageQuantity);
+        assert_eq!("amortized".parse::<SecondaryMetric>().unwrap(), SecondaryMetric::Cost(CostMetric::Amortized));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let group = |service: &str, amount: &str| {
+            Group::builder()
+                .keys(service)
+                .metrics("UnblendedCost", MetricValue::builder().amount(amount).unit("USD").build())
+                .build()
+        };
+        let results = vec![
+            ResultByTime::builder().groups(group("AWS Lambda", "1.5")).groups(group("Amazon S3", "0.5")).build(),
+            ResultByTime::builder().groups(group("AWS Lambda", "2.0")).build(),
+        ];
+        let groups = aggregate(results);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(get_cost(&groups[0], CostMetric::Unblended), 3.5);
+        assert_eq!(get_cost(&groups[1], CostMetric::Unblended), 0.5);
+    }
+
+    #[test]
+    fn test_check_unit() {
+        assert!(check_unit(&MetricValue::builder().amount("1.0").unit("USD").build()).is_ok());
+        assert!(check_unit(&MetricValue::builder().amount("1.0").build()).is_ok());
+        let error = check_unit(&MetricValue::builder().amount("150").unit("JPY").build()).unwrap_err();
+        assert!(error.to_string().contains("JPY"));
+
+        let group = |unit: &str| {
+            Group::builder()
+                .keys("AWS Lambda")
+                .metrics("UnblendedCost", MetricValue::builder().amount("150").unit(unit).build())
+                .build()
+        };
+        assert!(check_units(&[group("USD")], "UnblendedCost").is_ok());
+        assert!(check_units(&[group("USD"), group("JPY")], "UnblendedCost").is_err());
+        assert!(check_units(&[group("JPY")], "AmortizedCost").is_ok());
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let regions = ["us-east-1", "ap-northeast-1", "eu-west-1", "us-west-2"].iter().zip([5.0, 3.0, 1.0, 0.5])
+            .map(|(name, amount)| ServiceCost::new(name.to_string(), amount))
+            .collect::<Vec<_>>();
+        let rolled_up = roll_up(regions.clone(), 2, OTHER_REGIONS);
+        assert_eq!(rolled_up.len(), 3);
+        assert_eq!(rolled_up[2], ServiceCost::new(OTHER_REGIONS.to_string(), 1.5));
+        assert_eq!(roll_up(regions, 4, OTHER_REGIONS).len(), 4);
+    }
+
+    #[test]
+    fn test_root_cause_label() {
+        let root_cause = RootCause::builder().region("us-east-1").usage_type("BoxUsage:m5.large").linked_account_name("").build();
+        assert_eq!(root_cause_label(&root_cause), "us-east-1 / BoxUsage:m5.large");
+    }
+
+    #[test]
+    fn test_spot_percent() {
+        let option = |purchase_type, amount| ServiceCost::new(purchase_option_label(purchase_type), amount);
+        let options = [option("On Demand Instances", 60.0), option("Spot Instances", 30.0), option("Savings Plans", 10.0)];
+        assert_eq!(options[1].name, "スポット");
+        assert_eq!(spot_percent(&options), Some(30.0));
+        assert_eq!(spot_percent(&[]), None);
+    }
+
+    #[test]
+    fn test_data_transfer_costs() {
+        let usage_type = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let transfers = data_transfer_costs(vec![
+            usage_type("APN1-DataTransfer-Out-Bytes", 3.0),
+            usage_type("APN1-BoxUsage:m5.large", 20.0),
+            usage_type("APN1-NatGateway-Bytes", 4.5),
+            usage_type("APN1-DataTransfer-Regional-Bytes", 1.0),
+            usage_type("JP-DataTransfer-Out-Bytes", 2.0),
+            usage_type("APN1-USE1-AWS-Out-Bytes", 0.0),
+        ]);
+        let names: Vec<_> = transfers.iter().map(|transfer| (transfer.name.as_str(), transfer.amount)).collect();
+        assert_eq!(names, [("インターネットへの転送", 5.0), ("NAT ゲートウェイのデータ処理", 4.5), ("AZ 間の転送", 1.0)]);
+    }
+
+    #[test]
+    fn test_ai_spotlight_filters() {
+        assert!(is_ai_service("Amazon Bedrock"));
+        assert!(is_ai_service("Claude 3.5 Sonnet (Amazon Bedrock Edition)"));
+        assert!(is_ai_service("Amazon SageMaker"));
+        assert!(!is_ai_service("Amazon Simple Storage Service"));
+
+        assert!(is_accelerated_usage_type("APN1-BoxUsage:g5.xlarge"));
+        assert!(is_accelerated_usage_type("BoxUsage:p4d.24xlarge"));
+        assert!(is_accelerated_usage_type("USW2-SpotUsage:inf2.xlarge"));
+        assert!(is_accelerated_usage_type("USE1-DedicatedUsage:trn1.32xlarge"));
+        assert!(!is_accelerated_usage_type("APN1-BoxUsage:m5.large"));
+        assert!(is_accelerated_usage_type("APN1-BoxUsage:gr6.4xlarge"));
+        assert!(!is_accelerated_usage_type("APN1-EBS:VolumeUsage.gp3"));
+        assert!(!is_accelerated_usage_type("APN1-DataTransfer-Out-Bytes"));
+    }
+
+    #[test]
+    fn test_lambda_function_name() {
+        assert_eq!(lambda_function_name("arn:aws:lambda:ap-northeast-1:123456789012:function:billing-notification"), "billing-notification");
+        assert_eq!(lambda_function_name("NoResourceId"), "NoResourceId");
+    }
+
+    #[test]
+    fn test_cloudwatch_category() {
+        let usage_type = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let categories = sum_by_category(vec![
+            usage_type("APN1-DataProcessing-Bytes", 30.0),
+            usage_type("APN1-TimedStorage-ByteHrs", 4.0),
+            usage_type("APN1-CW:Requests", 1.0),
+            usage_type("APN1-VendedLog-Bytes", 5.0),
+            usage_type("APN1-CW:MetricMonitorUsage", 3.0),
+        ], cloudwatch_category);
+        let names: Vec<_> = categories.iter().map(|category| (category.name.as_str(), category.amount)).collect();
+        assert_eq!(names, [("ログの取り込み", 35.0), ("ログの保存", 4.0), ("メトリクス", 3.0), ("API 呼び出し", 1.0)]);
+    }
+
+    #[test]
+    fn test_quarter_start() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+        assert_eq!(quarter_start(date(1, 1)), Some(date(1, 1)));
+        assert_eq!(quarter_start(date(6, 30)), Some(date(4, 1)));
+        assert_eq!(quarter_start(date(10, 16)), Some(date(10, 1)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cost_and_usage() {
+        let result = cost_explorer().await.fetch_cost_and_usage(chrono::Utc::now().date_naive()).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_current_month_cost_forecast() {
+        let result = cost_explorer().await.fetch_current_month_cost_forecast(chrono::Utc::now().date_naive()).await;
+        println!("{:?}", result);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_current_month_cost() {
+        let result = cost_explorer().await.fetch_current_month_cost(chrono::Utc::now().date_naive()).await;
+        println!("{:?}", result);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_last_month_cost() {
+        let result = cost_explorer().await.fetch_last_month_cost(chrono::Utc::now().date_naive()).await;
+        println!("{:?}", result);
+        assert!(result.is_ok());
+    }
+}
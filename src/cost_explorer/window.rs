@@ -0,0 +1,147 @@
+use aws_sdk_costexplorer::types::Granularity;
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Deserialize;
+
+/// ランキングと内訳を集計する期間 [start, end)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub granularity: WindowGranularity,
+}
+
+/// 期間を問い合わせる粒度。期間内の結果は合計する
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowGranularity {
+    #[default]
+    Daily,
+    Monthly,
+}
+
+impl WindowGranularity {
+    pub fn to_granularity(self) -> Granularity {
+        match self {
+            Self::Daily => Granularity::Daily,
+            Self::Monthly => Granularity::Monthly,
+        }
+    }
+}
+
+impl Window {
+    /// `days` 日前の 1 日
+    pub fn days_ago(today: NaiveDate, days: u32) -> Self {
+        let start = today - chrono::Duration::days(days.into());
+        Self { start, end: start + chrono::Duration::days(1), granularity: WindowGranularity::Daily }
+    }
+
+    /// 毎月 `start_day` 日に始まる 1 か月のうち、`date` を含む期間。`start_day` は 1〜28
+    pub fn month_containing(date: NaiveDate, start_day: u32) -> Option<Self> {
+        let start = date.with_day(start_day)?;
+        let start = if start > date { start.checked_sub_months(Months::new(1))? } else { start };
+        Some(Self { start, end: start.checked_add_months(Months::new(1))?, granularity: WindowGranularity::Monthly })
+    }
+
+    /// 期間の日数
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days()
+    }
+
+    /// `days` 日前にずらした期間
+    pub fn shifted(&self, days: i64) -> Self {
+        let offset = chrono::Duration::days(days);
+        Self { start: self.start - offset, end: self.end - offset, granularity: self.granularity }
+    }
+
+    /// 暦月ちょうどの期間なら月数。月の途中で始まるか終わる期間なら `None`
+    pub fn whole_months(&self) -> Option<u32> {
+        if self.start.day() != 1 || self.end.day() != 1 {
+            return None;
+        }
+        let months = (self.end.year() - self.start.year()) * 12 + self.end.month() as i32 - self.start.month() as i32;
+        u32::try_from(months).ok().filter(|months| *months > 0)
+    }
+
+    /// 直前の同じ長さの期間。暦月ちょうどの期間なら、日数ではなく月単位でずらす
+    pub fn previous(&self) -> Self {
+        let shifted = self.whole_months().and_then(|months| {
+            let months = Months::new(months);
+            Some(Self { start: self.start.checked_sub_months(months)?, end: self.end.checked_sub_months(months)?, granularity: self.granularity })
+        });
+        shifted.unwrap_or_else(|| self.shifted(self.days()))
+    }
+
+    /// 期間の初日から 1 か月前の同じ日 (なければ月末) までの日数
+    pub fn days_since_last_month(&self) -> i64 {
+        let last_month = self.start.checked_sub_months(Months::new(1)).unwrap_or(self.start);
+        (self.start - last_month).num_days()
+    }
+
+    /// レポートに表示する期間 (例: `2026-10-01〜2026-10-07`)。終了日は期間に含まれる最後の日にする
+    pub fn label(&self) -> String {
+        let last = self.end.pred_opt().unwrap_or(self.end);
+        if last == self.start {
+            self.start.to_string()
+        } else {
+            format!("{}〜{last}", self.start)
+        }
+    }
+}
+
+/// `days` 日前の表示名
+pub fn days_ago_label(days: u32) -> String {
+    match days {
+        1 => "昨日".to_string(),
+        2 => "前々日".to_string(),
+        days => format!("{days}日前"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let window = Window { start: date(1), end: date(8), granularity: WindowGranularity::Daily };
+        assert_eq!(window.label(), "2026-10-01〜2026-10-07");
+        assert_eq!(Window::days_ago(date(3), 2).label(), "2026-10-01");
+    }
+
+    #[test]
+    fn test_shifted() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let window = Window { start: date(8), end: date(15), granularity: WindowGranularity::Daily };
+        assert_eq!(window.days(), 7);
+        assert_eq!(window.shifted(window.days()), Window { start: date(1), end: date(8), granularity: WindowGranularity::Daily });
+    }
+
+    #[test]
+    fn test_previous() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+        let september = Window { start: date(9, 1), end: date(10, 1), granularity: WindowGranularity::Monthly };
+        assert_eq!(september.whole_months(), Some(1));
+        assert_eq!(september.previous(), Window { start: date(8, 1), end: date(9, 1), granularity: WindowGranularity::Monthly });
+        let week = Window { start: date(10, 8), end: date(10, 15), granularity: WindowGranularity::Daily };
+        assert_eq!(week.whole_months(), None);
+        assert_eq!(week.previous(), week.shifted(7));
+    }
+
+    #[test]
+    fn test_month_containing() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+        let month = |start, end| Window { start, end, granularity: WindowGranularity::Monthly };
+        assert_eq!(Window::month_containing(date(10, 16), 1), Some(month(date(10, 1), date(11, 1))));
+        assert_eq!(Window::month_containing(date(10, 16), 21), Some(month(date(9, 21), date(10, 21))));
+        assert_eq!(Window::month_containing(date(10, 21), 21), Some(month(date(10, 21), date(11, 21))));
+        assert_eq!(Window::month_containing(date(1, 5), 21), Some(month(NaiveDate::from_ymd_opt(2025, 12, 21).unwrap(), date(1, 21))));
+    }
+
+    #[test]
+    fn test_days_since_last_month() {
+        let window = |month, day| Window::days_ago(NaiveDate::from_ymd_opt(2026, month, day).unwrap(), 0);
+        assert_eq!(window(10, 15).days_since_last_month(), 30);
+        assert_eq!(window(3, 31).days_since_last_month(), 31);
+    }
+}
@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use aws_sdk_athena as athena;
+use aws_sdk_athena::types::{QueryExecutionContext, QueryExecutionState, ResultConfiguration};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::config::{env_parse, env_required, env_var};
+use crate::report::{Breakdown, ServiceCost};
+
+/// クエリの完了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// クエリの完了を待つ回数の上限。Lambda のタイムアウトより先に打ち切る
+const MAX_POLLS: usize = 120;
+
+/// 問い合わせるクエリを設定しなかったときに使う、リソース別の料金の上位 10 件
+const DEFAULT_QUERY: &str = r#"SELECT line_item_resource_id, SUM(line_item_unblended_cost)
+FROM "{database}"."{table}"
+WHERE line_item_usage_start_date >= TIMESTAMP '{start} 00:00:00'
+  AND line_item_usage_start_date < TIMESTAMP '{end} 00:00:00'
+  AND line_item_resource_id <> ''
+GROUP BY 1
+ORDER BY 2 DESC
+LIMIT 10"#;
+
+/// CUR を料金の取得元にするときの、サービス別の料金
+const SERVICE_QUERY: &str = r#"SELECT COALESCE(NULLIF(product_product_name, ''), line_item_product_code), SUM(line_item_unblended_cost)
+FROM "{database}"."{table}"
+WHERE line_item_usage_start_date >= TIMESTAMP '{start} 00:00:00'
+  AND line_item_usage_start_date < TIMESTAMP '{end} 00:00:00'
+GROUP BY 1
+ORDER BY 2 DESC"#;
+
+/// S3 のバケット別の料金の上位 `{limit}` 件。S3 はリソース ID がバケット名になる
+const S3_BUCKET_QUERY: &str = r#"SELECT line_item_resource_id, SUM(line_item_unblended_cost)
+FROM "{database}"."{table}"
+WHERE line_item_usage_start_date >= TIMESTAMP '{start} 00:00:00'
+  AND line_item_usage_start_date < TIMESTAMP '{end} 00:00:00'
+  AND line_item_product_code = 'AmazonS3'
+  AND line_item_resource_id <> ''
+GROUP BY 1
+ORDER BY 2 DESC
+LIMIT {limit}"#;
+
+/// 期間の合計料金
+const TOTAL_QUERY: &str = r#"SELECT 'total', SUM(line_item_unblended_cost)
+FROM "{database}"."{table}"
+WHERE line_item_usage_start_date >= TIMESTAMP '{start} 00:00:00'
+  AND line_item_usage_start_date < TIMESTAMP '{end} 00:00:00'"#;
+
+/// Athena で問い合わせる CUR (Cost and Usage Report) の設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurConfig {
+    /// CUR のテーブルがある Glue データベース
+    pub database: String,
+    pub table: String,
+    /// Athena のワークグループ
+    #[serde(default = "default_workgroup")]
+    pub workgroup: String,
+    /// クエリ結果の出力先 (`s3://...`)。ワークグループで設定しているなら不要
+    pub output_location: Option<String>,
+    /// 内訳ごとのクエリ。未設定ならリソース別の料金の上位を載せる
+    #[serde(default)]
+    pub queries: Vec<CurQuery>,
+    /// S3 のバケット別の料金を載せる件数。未設定なら載せない
+    pub s3_bucket_count: Option<usize>,
+}
+
+/// 1 つの内訳を作るクエリ。1 列目を項目名、2 列目を料金 (USD) として読む。
+/// `{database}`・`{table}`・`{start}`・`{end}` (集計期間、end は含まない) を置き換える
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurQuery {
+    pub title: String,
+    pub sql: String,
+}
+
+fn default_workgroup() -> String {
+    "primary".to_string()
+}
+
+impl CurConfig {
+    /// 環境変数 `CUR_DATABASE`・`CUR_TABLE`・`CUR_WORKGROUP`・`CUR_OUTPUT_LOCATION`・`CUR_S3_BUCKET_COUNT` から読み込む。
+    /// データベースが未設定なら `None`
+    pub fn from_env() -> Result<Option<Self>, MyError> {
+        let Some(database) = env_var("", "CUR_DATABASE") else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            database,
+            table: env_required("", "CUR_TABLE", "CUR_DATABASE")?,
+            workgroup: env_var("", "CUR_WORKGROUP").unwrap_or_else(default_workgroup),
+            output_location: env_var("", "CUR_OUTPUT_LOCATION"),
+            queries: Vec::new(),
+            s3_bucket_count: env_parse("", "CUR_S3_BUCKET_COUNT")?,
+        }))
+    }
+
+    fn queries(&self) -> Vec<CurQuery> {
+        let mut queries = if self.queries.is_empty() {
+            vec![CurQuery { title: "リソース別料金 (CUR)".to_string(), sql: DEFAULT_QUERY.to_string() }]
+        } else {
+            self.queries.clone()
+        };
+        if let Some(count) = self.s3_bucket_count {
+            queries.push(CurQuery { title: "S3 のバケット別料金 (CUR)".to_string(), sql: S3_BUCKET_QUERY.replace("{limit}", &count.to_string()) });
+        }
+        queries
+    }
+
+    /// クエリのテンプレートを置き換える
+    fn render(&self, sql: &str, start: NaiveDate, end: NaiveDate) -> String {
+        sql.replace("{database}", &self.database)
+            .replace("{table}", &self.table)
+            .replace("{start}", &start.to_string())
+            .replace("{end}", &end.to_string())
+    }
+}
+
+/// CUR を Athena で問い合わせ、期間 [start, end) の内訳を返す
+pub async fn fetch_breakdowns(config: &CurConfig, start: NaiveDate, end: NaiveDate) -> Result<Vec<Breakdown>, MyError> {
+    let client = client().await;
+    let mut breakdowns = Vec::new();
+    for query in config.queries() {
+        let items = fetch_costs(&client, config, &config.render(&query.sql, start, end)).await?;
+        breakdowns.push(Breakdown { title: query.title, items });
+    }
+    Ok(breakdowns)
+}
+
+/// 期間 [start, end) のサービス別の料金を高い順に返す
+pub async fn fetch_service_costs(config: &CurConfig, start: NaiveDate, end: NaiveDate) -> Result<Vec<ServiceCost>, MyError> {
+    fetch_costs(&client().await, config, &config.render(SERVICE_QUERY, start, end)).await
+}
+
+/// 期間 [start, end) の合計料金を返す。データがなければ 0
+pub async fn fetch_total(config: &CurConfig, start: NaiveDate, end: NaiveDate) -> Result<f64, MyError> {
+    let costs = fetch_costs(&client().await, config, &config.render(TOTAL_QUERY, start, end)).await?;
+    Ok(costs.first().map(|cost| cost.amount).unwrap_or(0.0))
+}
+
+async fn client() -> athena::Client {
+    let sdk_config = aws_config::load_from_env().await;
+    athena::Client::new(&sdk_config)
+}
+
+/// 1 列目を項目名、2 列目を料金として読む。どちらかが空の行は飛ばす
+async fn fetch_costs(client: &athena::Client, config: &CurConfig, sql: &str) -> Result<Vec<ServiceCost>, MyError> {
+    let rows = run_query(client, config, sql).await?;
+    Ok(rows.iter()
+        .filter_map(|row| {
+            let name = row.first()?.clone()?;
+            let amount = row.get(1)?.as_deref()?.parse::<f64>().ok()?;
+            Some(ServiceCost::new(name, amount))
+        })
+        .collect())
+}
+
+/// クエリを実行して完了を待ち、見出しの行を除いた結果を返す
+async fn run_query(client: &athena::Client, config: &CurConfig, sql: &str) -> Result<Vec<Vec<Option<String>>>, MyError> {
+    let execution_id = client.start_query_execution()
+        .query_string(sql)
+        .work_group(&config.workgroup)
+        .query_execution_context(QueryExecutionContext::builder().database(&config.database).build())
+        .set_result_configuration(config.output_location.as_ref().map(|location| ResultConfiguration::builder().output_location(location).build()))
+        .send()
+        .await?
+        .query_execution_id
+        .ok_or("Athena のクエリ ID が返りませんでした")?;
+
+    for _ in 0..MAX_POLLS {
+        let status = client.get_query_execution().query_execution_id(&execution_id).send().await?
+            .query_execution
+            .and_then(|execution| execution.status);
+        match status.as_ref().and_then(|status| status.state.as_ref()) {
+            Some(QueryExecutionState::Succeeded) => {
+                let pages: Vec<_> = client.get_query_results().query_execution_id(&execution_id).into_paginator().send().try_collect().await?;
+                let rows = pages.into_iter()
+                    .flat_map(|page| page.result_set.and_then(|result_set| result_set.rows).unwrap_or_default())
+                    .skip(1)
+                    .map(|row| row.data.unwrap_or_default().into_iter().map(|datum| datum.var_char_value).collect())
+                    .collect();
+                return Ok(rows);
+            }
+            Some(QueryExecutionState::Failed | QueryExecutionState::Cancelled) => {
+                let reason = status.and_then(|status| status.state_change_reason).unwrap_or_default();
+                return Err(format!("Athena のクエリ {execution_id} が失敗しました: {reason}").into());
+            }
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+    Err(format!("Athena のクエリ {execution_id} が {} 秒以内に終わりませんでした", MAX_POLLS as u64 * POLL_INTERVAL.as_secs()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let config = CurConfig {
+            database: "cur".to_string(),
+            table: "billing".to_string(),
+            workgroup: default_workgroup(),
+            output_location: None,
+            queries: Vec::new(),
+            s3_bucket_count: None,
+        };
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let sql = config.render(&config.queries()[0].sql, date(1), date(2));
+        assert!(sql.contains(r#"FROM "cur"."billing""#));
+        assert!(sql.contains("TIMESTAMP '2026-10-01 00:00:00'"));
+        assert!(sql.contains("< TIMESTAMP '2026-10-02 00:00:00'"));
+
+        let config = CurConfig { s3_bucket_count: Some(5), ..config };
+        let queries = config.queries();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[1].title, "S3 のバケット別料金 (CUR)");
+        assert!(queries[1].sql.contains("line_item_product_code = 'AmazonS3'") && queries[1].sql.ends_with("LIMIT 5"));
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::MyError;
+use crate::config::Config;
+use crate::cost_explorer::window::{Window, WindowGranularity};
+use crate::report::ReportType;
+
+/// EventBridge のイベントの `detail` で指定する、この実行だけの設定
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ReportRequest {
+    /// レポートの種類 (`daily`・`weekly`・`monthly`)。スケジュールごとに変えれば 1 つの関数ですべてのレポートを送れる
+    pub report_type: Option<ReportType>,
+    /// ランキングに表示するサービス数
+    pub display_count: Option<usize>,
+    /// 通知せずに送信内容を返す
+    pub dry_run: Option<bool>,
+    /// 前々日の代わりに集計する期間の開始日 (例: `2026-10-01`)
+    pub start: Option<NaiveDate>,
+    /// 集計する期間の終了日 (この日を含まない)。省略すると今日
+    pub end: Option<NaiveDate>,
+    /// 集計する期間を問い合わせる粒度 (`daily`・`monthly`)
+    pub granularity: Option<WindowGranularity>,
+}
+
+impl ReportRequest {
+    /// `detail` を読む。スケジュールによるイベントのように空なら既定値にする
+    pub fn from_detail(detail: &Value) -> Result<Self, MyError> {
+        if detail.is_null() {
+            return Ok(Self::default());
+        }
+        serde_json::from_value(detail.clone()).map_err(|e| format!("イベントの detail が不正です: {e}").into())
+    }
+
+    /// 設定を上書きして検証する
+    pub fn apply(&self, mut config: Config) -> Result<Config, MyError> {
+        if let Some(report_type) = self.report_type {
+            config.report_type = report_type;
+        }
+        if let Some(display_count) = self.display_count {
+            config.display_count = display_count;
+        }
+        if let Some(dry_run) = self.dry_run {
+            config.dry_run = dry_run;
+        }
+        if self.start.is_some() || self.end.is_some() || self.granularity.is_some() {
+            let start = self.start.ok_or("イベントの detail で end や granularity を指定する場合は start も必要です")?;
+            let end = self.end.unwrap_or_else(|| config.today());
+            config.cost_explorer.window = Some(Window { start, end, granularity: self.granularity.unwrap_or_default() });
+        }
+        let config = config.with_report_type();
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_from_detail() {
+        assert_eq!(ReportRequest::from_detail(&Value::Null).unwrap(), ReportRequest::default());
+        assert_eq!(ReportRequest::from_detail(&json!({})).unwrap(), ReportRequest::default());
+
+        let request = ReportRequest::from_detail(&json!({ "display_count": 10 })).unwrap();
+        assert_eq!(request.apply(Config::default()).unwrap().display_count, 10);
+        assert!(ReportRequest::from_detail(&json!({ "display_count": -1 })).is_err());
+        assert!(ReportRequest { display_count: Some(0), ..Default::default() }.apply(Config::default()).is_err());
+    }
+
+    #[test]
+    fn test_window() {
+        let request = ReportRequest::from_detail(&json!({ "start": "2026-10-01", "end": "2026-10-08" })).unwrap();
+        let window = request.apply(Config::default()).unwrap().cost_explorer.window.unwrap();
+        assert_eq!(window.label(), "2026-10-01〜2026-10-07");
+        assert_eq!(window.granularity, WindowGranularity::Daily);
+
+        assert!(ReportRequest::from_detail(&json!({ "end": "2026-10-08" })).unwrap().apply(Config::default()).is_err());
+        assert!(ReportRequest::from_detail(&json!({ "start": "2026-10-08", "end": "2026-10-01" })).unwrap().apply(Config::default()).is_err());
+    }
+
+    #[test]
+    fn test_report_type() {
+        let mut daily = Config::default();
+        daily.cost_explorer.purchase_recommendations = true;
+        daily.idle_resources = true;
+        let weekly = Config { report_type: ReportType::Weekly, ..daily.clone() };
+        let config = ReportRequest::default().apply(daily).unwrap();
+        assert!(!config.cost_explorer.purchase_recommendations && !config.idle_resources);
+        let config = ReportRequest::default().apply(weekly).unwrap();
+        let window = config.cost_explorer.window.unwrap();
+        assert_eq!(window.days(), 7);
+        assert_eq!(window.end, config.today() - chrono::Duration::days(1));
+        assert!(config.cost_explorer.compare_previous && config.cost_explorer.daily_chart);
+        assert!(config.cost_explorer.purchase_recommendations && config.idle_resources);
+
+        let request = ReportRequest::from_detail(&json!({ "report_type": "monthly" })).unwrap();
+        assert_eq!(request.report_type, Some(ReportType::Monthly));
+        let config = request.apply(Config::default()).unwrap();
+        assert_eq!(config.cost_explorer.window.unwrap().whole_months(), Some(1));
+        assert_eq!(config.display_count, 10);
+    }
+}
@@ -0,0 +1,136 @@
+use aws_sdk_cloudwatch as cloudwatch;
+use aws_sdk_cloudwatch::primitives::DateTime;
+use aws_sdk_cloudwatch::types::{Dimension, Statistic};
+use aws_sdk_ec2 as ec2;
+use aws_sdk_ec2::types::Filter;
+
+use crate::MyError;
+use crate::report::{Breakdown, ServiceCost};
+
+/// 1 か月の時間数
+const HOURS_PER_MONTH: f64 = 730.0;
+
+/// どこにも関連付けられていない Elastic IP の 1 時間あたりの料金 (USD)
+const ELASTIC_IP_HOURLY: f64 = 0.005;
+
+/// NAT ゲートウェイの 1 時間あたりの料金 (USD)。データ処理の料金は除く
+const NAT_GATEWAY_HOURLY: f64 = 0.045;
+
+/// この日数のあいだデータを送っていない NAT ゲートウェイを使われていないとみなす
+const NAT_GATEWAY_IDLE_DAYS: i64 = 7;
+
+/// 使われずに料金だけかかっているリソース
+#[derive(Debug, Clone, PartialEq)]
+struct IdleResource {
+    /// 種類と ID (例: `未接続の EBS ボリューム vol-0123 (gp3 100GB)`)
+    name: String,
+    /// 1 か月あたりの料金の見積もり (USD)
+    monthly_cost: f64,
+}
+
+/// EBS ボリュームの 1 GB・1 か月あたりの料金 (USD)。IOPS やスループットの追加分は含めない
+fn volume_price(volume_type: &str) -> f64 {
+    match volume_type {
+        "gp2" => 0.10,
+        "gp3" => 0.08,
+        "io1" | "io2" => 0.125,
+        "st1" => 0.045,
+        "sc1" => 0.015,
+        _ => 0.05,
+    }
+}
+
+/// 月額の高い順に並べた内訳を返す。なければ `None`
+fn to_breakdown(mut resources: Vec<IdleResource>) -> Option<Breakdown> {
+    if resources.is_empty() {
+        return None;
+    }
+    resources.sort_by(|a, b| b.monthly_cost.total_cmp(&a.monthly_cost));
+    Some(Breakdown {
+        title: format!("使われていないリソース {} 件の月額 (us-east-1 の単価で概算)", resources.len()),
+        items: resources.into_iter()
+            .map(|resource| ServiceCost::new(resource.name, resource.monthly_cost))
+            .collect(),
+    })
+}
+
+/// 未接続の EBS ボリューム・関連付けのない Elastic IP・データを送っていない NAT ゲートウェイを探し、月額を見積もった内訳を返す。
+/// なければ `None`
+pub async fn fetch_breakdown() -> Result<Option<Breakdown>, MyError> {
+    let sdk_config = aws_config::load_from_env().await;
+    let ec2 = ec2::Client::new(&sdk_config);
+    let cloudwatch = cloudwatch::Client::new(&sdk_config);
+    let mut resources = Vec::new();
+
+    let pages: Vec<_> = ec2.describe_volumes()
+        .filters(Filter::builder().name("status").values("available").build())
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?;
+    for volume in pages.into_iter().flat_map(|page| page.volumes.unwrap_or_default()) {
+        let volume_type = volume.volume_type.as_ref().map(|volume_type| volume_type.as_str().to_string()).unwrap_or_default();
+        let size = volume.size.unwrap_or(0);
+        resources.push(IdleResource {
+            name: format!("未接続の EBS ボリューム {} ({volume_type} {size}GB)", volume.volume_id.unwrap_or_default()),
+            monthly_cost: volume_price(&volume_type) * f64::from(size),
+        });
+    }
+
+    let addresses = ec2.describe_addresses().send().await?.addresses.unwrap_or_default();
+    for address in addresses.into_iter().filter(|address| address.association_id.is_none()) {
+        resources.push(IdleResource {
+            name: format!("関連付けのない Elastic IP {}", address.public_ip.unwrap_or_default()),
+            monthly_cost: ELASTIC_IP_HOURLY * HOURS_PER_MONTH,
+        });
+    }
+
+    let pages: Vec<_> = ec2.describe_nat_gateways()
+        .filter(Filter::builder().name("state").values("available").build())
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?;
+    for id in pages.into_iter().flat_map(|page| page.nat_gateways.unwrap_or_default()).filter_map(|gateway| gateway.nat_gateway_id) {
+        if bytes_sent(&cloudwatch, &id).await? == 0.0 {
+            resources.push(IdleResource {
+                name: format!("直近{NAT_GATEWAY_IDLE_DAYS}日データを送っていない NAT ゲートウェイ {id}"),
+                monthly_cost: NAT_GATEWAY_HOURLY * HOURS_PER_MONTH,
+            });
+        }
+    }
+    Ok(to_breakdown(resources))
+}
+
+/// NAT ゲートウェイが直近 `NAT_GATEWAY_IDLE_DAYS` 日に送ったバイト数
+async fn bytes_sent(client: &cloudwatch::Client, nat_gateway_id: &str) -> Result<f64, MyError> {
+    let end = chrono::Utc::now().timestamp();
+    let start = end - chrono::Duration::days(NAT_GATEWAY_IDLE_DAYS).num_seconds();
+    let output = client.get_metric_statistics()
+        .namespace("AWS/NATGateway")
+        .metric_name("BytesOutToDestination")
+        .dimensions(Dimension::builder().name("NatGatewayId").value(nat_gateway_id).build())
+        .start_time(DateTime::from_secs(start))
+        .end_time(DateTime::from_secs(end))
+        .period(i32::try_from(end - start)?)
+        .statistics(Statistic::Sum)
+        .send()
+        .await?;
+    Ok(output.datapoints.unwrap_or_default().iter().filter_map(|datapoint| datapoint.sum).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_breakdown() {
+        let volume = IdleResource { name: "未接続の EBS ボリューム vol-1 (gp3 100GB)".to_string(), monthly_cost: volume_price("gp3") * 100.0 };
+        let address = IdleResource { name: "関連付けのない Elastic IP 203.0.113.1".to_string(), monthly_cost: ELASTIC_IP_HOURLY * HOURS_PER_MONTH };
+        let breakdown = to_breakdown(vec![address, volume]).unwrap();
+        assert_eq!(breakdown.title, "使われていないリソース 2 件の月額 (us-east-1 の単価で概算)");
+        assert_eq!(breakdown.items[0].amount, 8.0);
+        assert_eq!(breakdown.items[1].name, "関連付けのない Elastic IP 203.0.113.1");
+        assert_eq!(to_breakdown(Vec::new()), None);
+    }
+}
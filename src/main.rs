@@ -1,169 +1,140 @@
-use std::fmt::Write;
 use aws_lambda_events::eventbridge::EventBridgeEvent;
-use aws_sdk_costexplorer as costexplorer;
-use aws_sdk_costexplorer::types::{DateInterval, Granularity, Group, GroupDefinition, GroupDefinitionType, Metric, MetricValue};
-use chrono::{Datelike, Months};
 use lambda_runtime::{service_fn, LambdaEvent};
 use lambda_runtime::tower::ServiceExt;
 use reqwest::Client;
-use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
-use serde_json::Value;
+use serde_json::{json, Value};
+
+use crate::config::{feature_flags, Config};
+use crate::event::ReportRequest;
+use crate::notifier::NotifierRegistry;
+use crate::provider::{CloudWatchProvider, CostProvider};
+use crate::report::{Exchange, Report, ReportType};
+
+mod alert;
+mod budgets;
+mod cloudwatch;
+mod config;
+mod cost_explorer;
+mod cur;
+mod event;
+mod idle;
+mod notifier;
+mod provider;
+mod report;
+mod secrets;
+mod slack_app;
 
 type MyError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 #[tokio::main]
 async fn main() -> Result<(), lambda_runtime::Error> {
-    lambda_runtime::run(service_fn(lambda_handler)).await?;
+    dotenvy::dotenv().ok();
+    // 設定の誤りは最初の実行を待たずにコールドスタートで失敗させる
+    let config = Config::load().await?;
+    let config = &config;
+    // 同じバイナリを用途ごとに別の Lambda としてデプロイし、HANDLER で処理を切り替える
+    match std::env::var("HANDLER").as_deref() {
+        Ok("slack_command") => lambda_runtime::run(service_fn(move |event| slack_app::command::handler(config, event))).await?,
+        Ok("slack_interaction") => lambda_runtime::run(service_fn(move |event| slack_app::interaction::handler(config, event))).await?,
+        _ => lambda_runtime::run(service_fn(move |event| lambda_handler(config, event))).await?,
+    }
     Ok(())
 }
 
 async fn lambda_handler(
-    _event: LambdaEvent<EventBridgeEvent<serde_json::Value>>,
-) -> Result<(), lambda_runtime::Error> {
-    let exchange_rate = fetch_exchange_rate().await?;
-    let cost_and_usages = fetch_cost_and_usage().await?;
-    let current_month_cost_forecast = fetch_current_month_cost_forecast().await?;
-
-    let total_cost: f64 = cost_and_usages.iter()
-        .filter_map(|group| group.metrics.as_ref())
-        .flat_map(|metrics| metrics.values().cloned().collect::<Vec<_>>())
-        .filter_map(|metric| metric.amount)
-        .filter_map(|amount| amount.parse::<f64>().ok())
-        .sum();
-    println!("total_cost: {}", total_cost);
-
-    let monthly_cost = fetch_current_month_cost().await?;
-
-    let formatted_total_cost = format_cost(total_cost, exchange_rate);
-    println!("formatted_total_cost: {}", formatted_total_cost);
-
-    let formatted_cost_per_service = format_service_costs(&cost_and_usages, exchange_rate, 5)?;
-    println!("formatted_cost_per_service: {}", formatted_cost_per_service);
-
-    let formatted_current_month_cost_forecast = format_cost(current_month_cost_forecast, exchange_rate);
-    println!("formatted_forecast: {}", formatted_current_month_cost_forecast);
-
-    let formatted_monthly_cost = format_cost(monthly_cost, exchange_rate);
-    println!("formatted_monthly_cost: {}", formatted_monthly_cost);
-
-    let content = format!("前々日料金:{formatted_total_cost}
---------------
-現時点料金:{formatted_monthly_cost}
-今月の予測:{formatted_current_month_cost_forecast}
-■前々日の料金ランキング
-{formatted_cost_per_service}
-");
+    config: &Config,
+    event: LambdaEvent<EventBridgeEvent<serde_json::Value>>,
+) -> Result<Value, lambda_runtime::Error> {
+    let request = ReportRequest::from_detail(&event.payload.detail)?;
+    let config = &request.apply(feature_flags::apply(config).await)?;
+    println!("{:?} のレポートを作成します", config.report_type);
+    let report = build_report(config).await?;
+
+    let content = report.to_text()?;
     println!("{}", content);
 
-    Ok(())
-}
-
-fn format_cost(cost_usd: f64, exchange_rate: f64) -> String {
-    let cost_jpy = cost_usd * exchange_rate;
-    let rounded_jpy = cost_jpy.round();
-    let rounded_usd = Decimal::from_f64(cost_usd).map(|d| d.round_dp(2)).unwrap_or_else(|| Decimal::ZERO);
-    format!("{rounded_jpy}円(${rounded_usd})")
-}
-
-fn format_service_costs(cost_and_usages: &[Group], exchange_rate: f64, display_count: i8) -> Result<String, MyError> {
-    let mut formatted_cost_per_service = String::new();
+    if !config.notifications_enabled && !config.dry_run {
+        println!("通知が無効 (NOTIFICATIONS_ENABLED=false) のため通知をスキップします");
+        return Ok(Value::Null);
+    }
+    // 週次・月次のレポートは料金が変わらなくても省かない
+    let quiet = config.quiet.as_ref().filter(|_| config.report_type == ReportType::Daily);
+    if quiet.is_some_and(|quiet| quiet.is_quiet(&report)) && !config.dry_run {
+        println!("料金が少なく直近の平均とも変わらないため通知をスキップします");
+        return Ok(Value::Null);
+    }
 
-    for cost in cost_and_usages.iter().take(display_count as usize) {
-        if let Some(keys) = &cost.keys {
-            if let Some(key) = keys.first() {
-                if let Some(metrics) = &cost.metrics {
-                    if let Some(formatted_cost) = metrics.get("UnblendedCost").and_then(|metric| compute_formatted_cost(metric, exchange_rate)) {
-                        writeln!(formatted_cost_per_service, "{:<50}:  {}", key, formatted_cost)?;
-                    }
-                }
-            }
+    let notifiers = NotifierRegistry::from_config(config).await?;
+    if config.dry_run {
+        return Ok(dry_run(&notifiers, &report, content));
+    }
+    if notifiers.is_empty() {
+        println!("通知先が設定されていないため通知をスキップします");
+    }
+    for delivery in notifiers.send_all(&report).await {
+        match delivery.result {
+            Ok(()) => println!("{} への通知が完了しました", delivery.channel),
+            Err(e) => eprintln!("{} への通知に失敗しました: {e}", delivery.channel),
         }
     }
-    Ok(format!("```\n{}\n```", formatted_cost_per_service))
-}
-
-fn compute_formatted_cost(metric: &MetricValue, exchange_rate: f64) -> Option<String> {
-    metric.amount.as_ref()
-        .and_then(|amount| amount.parse::<f64>().ok())
-        .map(|amount| format_cost(amount, exchange_rate))
-}
-
-/// 1 USD あたりの JPY の逆レートを返す
-/// Returns the inverse rate of JPY per USD
-async fn fetch_exchange_rate() -> Result<f64, MyError> {
-    let url = "https://www.floatrates.com/daily/jpy.json";
-    let json: Value = Client::new().get(url).send().await?.json().await?;
-    json["usd"]["inverseRate"].as_f64().ok_or_else(|| "USDレートをf64に変換できませんでした".into())
-}
 
-/// 2日前から昨日までの利用料金を返す
-async fn fetch_cost_and_usage() -> Result<Vec<Group>, MyError> {
-    let day_before_yesterday = chrono::Utc::now().date_naive() - chrono::Duration::days(2);
-    let yesterday = chrono::Utc::now().date_naive() - chrono::Duration::days(1);
-
-    let config = aws_config::load_from_env().await;
-    let client = costexplorer::Client::new(&config);
-    let result = client.get_cost_and_usage()
-        .time_period(DateInterval::builder().start(day_before_yesterday.to_string()).end(yesterday.to_string()).build()?)
-        .granularity(Granularity::Daily)
-        .metrics("UnblendedCost")
-        .group_by(GroupDefinition::builder().r#type(GroupDefinitionType::Dimension).key("SERVICE").build())
-        .send()
-        .await?;
-    let mut groups = result.results_by_time.and_then(|mut rbt| rbt.pop()).and_then(|first| first.groups).ok_or_else(|| "No groups found in the first result".to_string())?;
-    groups.sort_by(|a, b| {
-        let a_cost = get_unblended_cost(a);
-        let b_cost = get_unblended_cost(b);
-        b_cost.partial_cmp(&a_cost).unwrap()
-    });
-    println!("{:?}", groups);
-    Ok(groups)
+    Ok(Value::Null)
 }
 
-fn get_unblended_cost(group: &Group) -> f64 {
-    group.metrics.as_ref().and_then(|metrics| metrics.get("UnblendedCost")).and_then(|cost| cost.amount.as_ref()).and_then(|amount| amount.parse::<f64>().ok()).unwrap_or(0.0)
+/// 各通知先に送る内容をログに出し、呼び出し元にも返す
+fn dry_run(notifiers: &NotifierRegistry, report: &Report, text: String) -> Value {
+    let mut channels = serde_json::Map::new();
+    for preview in notifiers.preview_all(report) {
+        let payload = preview.payload.unwrap_or_else(|e| json!({ "error": e.to_string() }));
+        println!("[dry-run] {}: {payload}", preview.channel);
+        channels.insert(preview.channel.to_string(), payload);
+    }
+    json!({ "dry_run": true, "text": text, "channels": channels })
 }
 
-async fn fetch_current_month_cost_forecast() -> Result<f64, MyError> {
-    let today = chrono::Utc::now().date_naive();
-    let next_month_1st = chrono::Utc::now().date_naive().checked_add_months(Months::new(1)).and_then(|d| d.with_day(1)).ok_or_else(|| "Failed to calculate the first day of next month".to_string())?;
-    let config = aws_config::load_from_env().await;
-    let client = costexplorer::Client::new(&config);
-    let result = client.get_cost_forecast().time_period(DateInterval::builder().start(today.to_string()).end(next_month_1st.to_string()).build()?).metric(Metric::UnblendedCost).granularity(Granularity::Monthly).send().await?;
-    Ok(result.total.and_then(|total| total.amount).and_then(|amount| amount.parse::<f64>().ok()).ok_or_else(|| "Failed to parse the forecasted cost".to_string())?)
+/// 設定した取得元の料金と為替レートからレポートを組み立てる。取得元を使えないときは
+/// CloudWatch の請求見込み額から合計だけの縮小版を組み立てる
+pub(crate) async fn build_report(config: &Config) -> Result<Report, MyError> {
+    let exchange = fetch_exchange(&config.currency).await?;
+    let today = config.today();
+    let provider = provider::from_config(config).await?;
+    let mut costs = match provider.fetch(today).await {
+        Err(e) if provider.is_unavailable(&e) => {
+            eprintln!("{} を使えないため CloudWatch の請求見込み額で集計します: {e}", provider.name());
+            let mut costs = CloudWatchProvider::new(config).fetch(today).await.map_err(|fallback_error| {
+                eprintln!("CloudWatch の請求見込み額も取得できませんでした: {fallback_error}");
+                e
+            })?;
+            costs.notice = Some(format!("{} を利用できないため、CloudWatch の請求見込み額から合計だけを載せています", provider.name()));
+            costs
+        }
+        result => result?,
+    };
+    if let Some(budgets) = &config.budgets {
+        costs.breakdowns.push(budgets::fetch_breakdown(budgets).await?);
+    }
+    if config.idle_resources {
+        costs.breakdowns.extend(idle::fetch_breakdown().await?);
+    }
+    if let Some(count) = config.log_group_count {
+        let window = config.cost_explorer.window(today);
+        costs.breakdowns.extend(cloudwatch::fetch_log_group_ingestion(window.start, window.end, &config.cost_explorer.period_label(), count).await?);
+    }
+    let mut report = costs.into_report(exchange, config);
+    report.alerts = alert::evaluate(&report, &config.thresholds);
+    Ok(report)
 }
 
-async fn fetch_current_month_cost() -> Result<f64, MyError> {
-    let current_month_1th = chrono::Utc::now().date_naive().with_day(1).ok_or_else(|| "Failed to calculate the first day of this month".to_string())?;
-    let next_month_1st = chrono::Utc::now().date_naive()
-        .checked_add_months(Months::new(1))
-        .and_then(|d| d.with_day(1))
-        .ok_or_else(|| "Failed to calculate the first day of next month".to_string())?;
-
-    let config = aws_config::load_from_env().await;
-    let client = costexplorer::Client::new(&config);
-    let result = client.get_cost_and_usage()
-        .time_period(
-            DateInterval::builder()
-                .start(current_month_1th.to_string())
-                .end(next_month_1st.to_string())
-                .build()?
-        )
-        .granularity(Granularity::Monthly)
-        .metrics("UnblendedCost")
-        .send()
-        .await?;
-
-    let total_cost = result.results_by_time
-        .and_then(|results_by_time| results_by_time.first().cloned())
-        .and_then(|result_by_time| result_by_time.total)
-        .and_then(|total| total.get("UnblendedCost").cloned())
-        .and_then(|cost| cost.amount)
-        .and_then(|amount| amount.parse::<f64>().ok())
-        .ok_or_else(|| "Failed to extract this month cost amount")?;
-
-    Ok(total_cost)
+/// 1 USD あたりの表示通貨の逆レートを返す。表示通貨が USD なら取得しない
+/// Returns the inverse rate of the display currency per USD
+async fn fetch_exchange(currency: &str) -> Result<Exchange, MyError> {
+    if currency == "USD" {
+        return Ok(Exchange::usd());
+    }
+    let url = format!("https://www.floatrates.com/daily/{}.json", currency.to_ascii_lowercase());
+    let json: Value = Client::new().get(&url).send().await?.json().await?;
+    let rate = json["usd"]["inverseRate"].as_f64().ok_or_else(|| format!("{currency} の USD レートを f64 に変換できませんでした"))?;
+    Ok(Exchange { currency: currency.to_string(), rate })
 }
 
 #[cfg(test)]
@@ -173,33 +144,13 @@ mod tests {
     #[tokio::test]
     async fn test_lambda_handler() {
         let event = LambdaEvent::new(EventBridgeEvent::default(), Default::default());
-        let result = lambda_handler(event).await;
-        assert!(result.is_ok());
-    }
-
-    #[tokio::test]
-    async fn test_fetch_exchange_rate() {
-        let result = fetch_exchange_rate().await;
-        assert!(result.is_ok());
-    }
-
-    #[tokio::test]
-    async fn test_fetch_cost_and_usage() {
-        let result = fetch_cost_and_usage().await;
-        assert!(result.is_ok());
-    }
-
-    #[tokio::test]
-    async fn test_fetch_current_month_cost_forecast() {
-        let result = fetch_current_month_cost_forecast().await;
-        println!("{:?}", result);
+        let result = lambda_handler(&Config::from_env().unwrap(), event).await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
-    async fn test_fetch_current_month_cost() {
-        let result = fetch_current_month_cost().await;
-        println!("{:?}", result);
+    async fn test_fetch_exchange() {
+        let result = fetch_exchange("JPY").await;
         assert!(result.is_ok());
     }
 }
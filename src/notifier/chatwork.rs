@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::{format_ranking_lines, Report};
+use crate::secrets::Secret;
+
+/// Chatwork の設定
+#[derive(Clone, Deserialize)]
+pub struct ChatworkConfig {
+    pub room_id: String,
+    pub api_token: Secret,
+}
+
+impl ChatworkConfig {
+    /// 環境変数 `{prefix}CHATWORK_ROOM_ID`・`{prefix}CHATWORK_API_TOKEN`・`{prefix}CHATWORK_API_TOKEN_SECRET_ID` から読み込む。
+    /// ルーム ID が未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let Some(room_id) = env_var(prefix, "CHATWORK_ROOM_ID") else {
+            return Ok(None);
+        };
+        let api_token = Secret::from_env(prefix, "CHATWORK_API_TOKEN")
+            .ok_or_else(|| format!("設定エラー: {prefix}CHATWORK_ROOM_ID を設定する場合は {prefix}CHATWORK_API_TOKEN か {prefix}CHATWORK_API_TOKEN_SECRET_ID も必要です"))?;
+        Ok(Some(Self { room_id, api_token }))
+    }
+}
+
+/// Chatwork のルームへのメッセージ投稿
+pub struct ChatworkNotifier {
+    client: Client,
+    api_token: String,
+    room_id: String,
+}
+
+impl ChatworkNotifier {
+    pub fn new(api_token: impl Into<String>, room_id: impl Into<String>) -> Self {
+        Self { client: Client::new(), api_token: api_token.into(), room_id: room_id.into() }
+    }
+
+    /// API トークンがシークレットで指定されていれば取得して生成する
+    pub async fn from_config(config: &ChatworkConfig) -> Result<Self, MyError> {
+        Ok(Self::new(config.api_token.resolve().await?, &config.room_id))
+    }
+}
+
+#[async_trait]
+impl Notifier for ChatworkNotifier {
+    fn name(&self) -> &'static str {
+        "chatwork"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let url = format!("https://api.chatwork.com/v2/rooms/{}/messages", self.room_id);
+        let request = self.client.post(&url)
+            .header("X-ChatWorkToken", &self.api_token)
+            .form(&[("body", render(report)?)]);
+        send_with_retry(request, &url).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(Value::String(render(report)?))
+    }
+}
+
+/// 日次レポートを Chatwork 記法 ([info] / [code]) に変換する
+fn render(report: &Report) -> Result<String, MyError> {
+    let ranking = format_ranking_lines(&report.numbered_ranking(), &report.exchange, usize::MAX, true)?;
+    Ok(format!("[info][title]{}[/title]{}[/info]{}
+[code]{ranking}[/code]", report.title(), report.headline(), report.ranking_title()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_render() {
+        let body = render(&fixtures::report()).unwrap();
+        assert!(body.starts_with("[info][title]"));
+        assert!(body.contains("[code]1. Amazon Elastic Compute Cloud"));
+        assert!(!body.contains("```"));
+    }
+}
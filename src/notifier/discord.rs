@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::notifier::{post_json_with_retry, Notifier};
+use crate::report::Report;
+use crate::secrets::Secret;
+
+/// 予測が先月を上回るときの埋め込みの色
+const COLOR_OVER: u32 = 0xE74C3C;
+/// 予測が先月以下のときの埋め込みの色
+const COLOR_UNDER: u32 = 0x2ECC71;
+
+/// Discord Webhook の設定
+#[derive(Clone, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_url: Secret,
+}
+
+impl DiscordConfig {
+    /// 環境変数 `{prefix}DISCORD_WEBHOOK_URL` (または `{prefix}DISCORD_WEBHOOK_URL_SECRET_ID`) から読み込む。未設定なら `None`
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        Secret::from_env(prefix, "DISCORD_WEBHOOK_URL").map(|webhook_url| Self { webhook_url })
+    }
+}
+
+/// Discord Webhook への通知
+pub struct DiscordNotifier {
+    client: Client,
+    webhook_url: String,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self { client: Client::new(), webhook_url: webhook_url.into() }
+    }
+
+    /// Webhook URL がシークレットで指定されていれば取得して生成する
+    pub async fn from_config(config: &DiscordConfig) -> Result<Self, MyError> {
+        Ok(Self::new(config.webhook_url.resolve().await?))
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        post_json_with_retry(&self.client, &self.webhook_url, &self.preview(report)?).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({ "embeds": [render(report)] }))
+    }
+}
+
+/// 日次レポートを埋め込みに変換する。合計の項目は 1 つのフィールドにまとめ、ランキングはサービスごとのフィールドにする
+fn render(report: &Report) -> Value {
+    let totals: Vec<_> = report.totals().iter().map(|(label, cost)| format!("{label}: {cost}")).collect();
+    let mut fields = vec![field("合計", &totals.join("\n"), false)];
+    fields.extend(report.numbered_ranking().iter().map(|service| field(&service.label(), &report.format_cost(service.amount), false)));
+
+    let color = if report.last_month_cost.is_some_and(|last_month_cost| report.forecast > last_month_cost) { COLOR_OVER } else { COLOR_UNDER };
+    json!({
+        "title": report.title(),
+        "color": color,
+        "fields": fields,
+    })
+}
+
+fn field(name: &str, value: &str, inline: bool) -> Value {
+    json!({ "name": name, "value": value, "inline": inline })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MAX_DISPLAY_COUNT;
+    use crate::report::{fixtures, BudgetRunway, Comparison, ServiceCost};
+
+    #[test]
+    fn test_render_color() {
+        let mut report = fixtures::report();
+        report.forecast = 281.0;
+        assert_eq!(render(&report)["color"], COLOR_OVER);
+        report.forecast = 279.0;
+        assert_eq!(render(&report)["color"], COLOR_UNDER);
+    }
+
+    #[test]
+    fn test_render_fields() {
+        let embed = render(&fixtures::report());
+        // 合計 1 件 + ランキング 3 件
+        assert_eq!(embed["fields"].as_array().unwrap().len(), 4);
+        assert_eq!(embed["fields"][0]["name"], "合計");
+        assert!(embed["fields"][0]["value"].as_str().unwrap().contains("\n現時点料金: 18000円($120)\n"));
+        assert_eq!(embed["fields"][1]["name"], "1. Amazon Elastic Compute Cloud - Compute");
+    }
+
+    #[test]
+    fn test_render_fields_within_limit() {
+        let runway = BudgetRunway { budget: 500.0, daily_burn: 12.0, remaining: 380.0, exhausted_on: None };
+        let report = Report {
+            services: (0..30).map(|i| ServiceCost::new(format!("Service {i}"), 30.0 - i as f64)).collect(),
+            display_count: MAX_DISPLAY_COUNT,
+            budget_runway: Some(runway),
+            comparisons: vec![Comparison { label: "前日比".to_string(), baseline: 10.0 }],
+            extrapolation: Some(300.0),
+            ..fixtures::report()
+        };
+        let fields = render(&report)["fields"].as_array().unwrap().len();
+        // 合計 1 件 + ランキング 20 件 + その他 1 件
+        assert_eq!(fields, 1 + MAX_DISPLAY_COUNT + 1);
+        // Discord の埋め込みのフィールド数の上限
+        assert!(fields <= 25);
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use aws_sdk_sesv2 as sesv2;
+use aws_sdk_sesv2::types::{Body, Content, Destination, EmailContent, Message};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::{env_required, env_var};
+use crate::notifier::Notifier;
+use crate::report::Report;
+
+/// SES によるメール通知の設定
+#[derive(Clone, Deserialize)]
+pub struct EmailConfig {
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl EmailConfig {
+    /// 環境変数 `{prefix}EMAIL_FROM` と `{prefix}EMAIL_TO` (カンマ区切り) から読み込む。`{prefix}EMAIL_TO` が未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let to: Vec<String> = env_var(prefix, "EMAIL_TO").unwrap_or_default()
+            .split(',')
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect();
+        if to.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { from: env_required(prefix, "EMAIL_FROM", "EMAIL_TO")?, to }))
+    }
+}
+
+/// SES による HTML メール通知 (テキスト版を併送する)
+pub struct EmailNotifier {
+    client: sesv2::Client,
+    config: EmailConfig,
+}
+
+impl EmailNotifier {
+    pub fn new(sdk_config: &SdkConfig, config: EmailConfig) -> Self {
+        Self { client: sesv2::Client::new(sdk_config), config }
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let message = Message::builder()
+            .subject(utf8(&report.title())?)
+            .body(Body::builder().text(utf8(&report.to_text()?)?).html(utf8(&render_html(report)?)?).build())
+            .build();
+        self.client.send_email()
+            .from_email_address(&self.config.from)
+            .destination(Destination::builder().set_to_addresses(Some(self.config.to.clone())).build())
+            .content(EmailContent::builder().simple(message).build())
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({
+            "to": self.config.to,
+            "subject": report.title(),
+            "text": report.to_text()?,
+            "html": render_html(report)?,
+        }))
+    }
+}
+
+fn utf8(data: &str) -> Result<Content, MyError> {
+    Ok(Content::builder().data(data).charset("UTF-8").build()?)
+}
+
+/// 日次レポートを HTML メール本文に変換する
+fn render_html(report: &Report) -> Result<String, MyError> {
+    let mut html = String::new();
+    writeln!(html, "<h2>{}</h2>", escape_html(&report.title()))?;
+    writeln!(html, "<table>")?;
+    for (label, cost) in report.totals() {
+        writeln!(html, "<tr><th align=\"left\">{label}</th><td align=\"right\">{}</td></tr>", escape_html(&cost))?;
+    }
+    writeln!(html, "</table>")?;
+    writeln!(html, "<h3>{}</h3>", escape_html(&report.ranking_title()))?;
+    writeln!(html, "<table border=\"1\" cellpadding=\"4\" style=\"border-collapse: collapse\">")?;
+    for service in report.numbered_ranking() {
+        writeln!(html, "<tr><td>{}</td><td align=\"right\">{}</td></tr>", escape_html(&service.label()), escape_html(&report.format_cost(service.amount)))?;
+    }
+    writeln!(html, "</table>")?;
+    for breakdown in &report.breakdowns {
+        writeln!(html, "<h3>■{}</h3>", escape_html(&breakdown.title))?;
+        writeln!(html, "<table border=\"1\" cellpadding=\"4\" style=\"border-collapse: collapse\">")?;
+        for item in &breakdown.items {
+            writeln!(html, "<tr><td>{}</td><td align=\"right\">{}</td></tr>", escape_html(&item.name), escape_html(&report.format_cost(item.amount)))?;
+        }
+        writeln!(html, "</table>")?;
+    }
+    Ok(html)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(&fixtures::report()).unwrap();
+        assert!(html.contains("<td>3. AWS Lambda</td>"));
+        assert!(html.contains("今月の予測"));
+    }
+}
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::{post_json_with_retry, Notifier};
+use crate::report::Report;
+
+/// Google Chat スペースの Incoming Webhook の設定
+#[derive(Clone, Deserialize)]
+pub struct GoogleChatConfig {
+    pub webhook_url: String,
+}
+
+impl GoogleChatConfig {
+    /// 環境変数 `{prefix}GOOGLE_CHAT_WEBHOOK_URL` から読み込む。未設定なら `None`
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        env_var(prefix, "GOOGLE_CHAT_WEBHOOK_URL").map(|webhook_url| Self { webhook_url })
+    }
+}
+
+/// Google Chat スペースの Incoming Webhook への通知
+pub struct GoogleChatNotifier {
+    client: Client,
+    config: GoogleChatConfig,
+}
+
+impl GoogleChatNotifier {
+    pub fn new(config: GoogleChatConfig) -> Self {
+        Self { client: Client::new(), config }
+    }
+}
+
+#[async_trait]
+impl Notifier for GoogleChatNotifier {
+    fn name(&self) -> &'static str {
+        "google_chat"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        post_json_with_retry(&self.client, &self.config.webhook_url, &render(report)).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(render(report))
+    }
+}
+
+/// 日次レポートを Cards v2 のメッセージに変換する
+fn render(report: &Report) -> Value {
+    let totals = report.totals()
+        .iter()
+        .map(|(label, cost)| decorated_text(label, cost))
+        .collect::<Vec<_>>();
+    let ranking = report.numbered_ranking()
+        .iter()
+        .map(|service| decorated_text(&service.label(), &report.format_cost(service.amount)))
+        .collect::<Vec<_>>();
+
+    json!({
+        "cardsV2": [{
+            "cardId": "billing-report",
+            "card": {
+                "header": { "title": report.title() },
+                "sections": [
+                    { "widgets": totals },
+                    { "header": report.ranking_title(), "widgets": ranking },
+                ],
+            },
+        }],
+    })
+}
+
+fn decorated_text(label: &str, value: &str) -> Value {
+    json!({ "decoratedText": { "topLabel": label, "text": value } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_render() {
+        let message = render(&fixtures::report());
+        let sections = &message["cardsV2"][0]["card"]["sections"];
+        assert_eq!(sections[0]["widgets"].as_array().unwrap().len(), 4);
+        assert_eq!(sections[1]["widgets"][2]["decoratedText"]["topLabel"], "3. AWS Lambda");
+    }
+}
@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::Report;
+use crate::secrets::Secret;
+
+const PUSH_URL: &str = "https://api.line.me/v2/bot/message/push";
+
+/// LINE Messaging API の設定
+#[derive(Clone, Deserialize)]
+pub struct LineConfig {
+    /// 送信先のグループ ID
+    pub to: String,
+    pub channel_token: Secret,
+}
+
+impl LineConfig {
+    /// 環境変数 `{prefix}LINE_TO` と `{prefix}LINE_CHANNEL_TOKEN` (または `{prefix}LINE_CHANNEL_TOKEN_SECRET_ID`) から読み込む。
+    /// 送信先が未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let Some(to) = env_var(prefix, "LINE_TO") else {
+            return Ok(None);
+        };
+        let channel_token = Secret::from_env(prefix, "LINE_CHANNEL_TOKEN")
+            .ok_or_else(|| format!("設定エラー: {prefix}LINE_TO を設定する場合は {prefix}LINE_CHANNEL_TOKEN か {prefix}LINE_CHANNEL_TOKEN_SECRET_ID も必要です"))?;
+        Ok(Some(Self { to, channel_token }))
+    }
+}
+
+/// LINE Messaging API の push メッセージによる通知
+pub struct LineNotifier {
+    client: Client,
+    channel_access_token: String,
+    to: String,
+}
+
+impl LineNotifier {
+    pub fn new(channel_access_token: impl Into<String>, to: impl Into<String>) -> Self {
+        Self { client: Client::new(), channel_access_token: channel_access_token.into(), to: to.into() }
+    }
+
+    /// チャネルアクセストークンがシークレットで指定されていれば取得して生成する
+    pub async fn from_config(config: &LineConfig) -> Result<Self, MyError> {
+        Ok(Self::new(config.channel_token.resolve().await?, &config.to))
+    }
+}
+
+#[async_trait]
+impl Notifier for LineNotifier {
+    fn name(&self) -> &'static str {
+        "line"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let request = self.client.post(PUSH_URL).bearer_auth(&self.channel_access_token).json(&self.preview(report)?);
+        send_with_retry(request, PUSH_URL).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({
+            "to": self.to,
+            "messages": [render(report)],
+        }))
+    }
+}
+
+/// 日次レポートを Flex Message に変換する
+fn render(report: &Report) -> Value {
+    let totals = report.totals();
+    let alt_text = totals.iter()
+        .map(|(label, cost)| format!("{label}:{cost}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut contents: Vec<Value> = totals.iter()
+        .map(|(label, cost)| row(label, cost, true))
+        .collect();
+    contents.push(json!({ "type": "separator", "margin": "md" }));
+    contents.push(json!({ "type": "text", "text": report.ranking_title(), "weight": "bold", "size": "sm", "margin": "md" }));
+    contents.extend(report.numbered_ranking().iter().map(|service| row(&service.label(), &report.format_cost(service.amount), false)));
+
+    json!({
+        "type": "flex",
+        "altText": alt_text,
+        "contents": {
+            "type": "bubble",
+            "header": {
+                "type": "box",
+                "layout": "vertical",
+                "contents": [{ "type": "text", "text": report.title(), "weight": "bold", "size": "lg" }],
+            },
+            "body": {
+                "type": "box",
+                "layout": "vertical",
+                "spacing": "sm",
+                "contents": contents,
+            },
+        },
+    })
+}
+
+fn row(label: &str, value: &str, bold: bool) -> Value {
+    json!({
+        "type": "box",
+        "layout": "horizontal",
+        "contents": [
+            { "type": "text", "text": label, "size": "xs", "color": "#555555", "flex": 3, "wrap": true },
+            { "type": "text", "text": value, "size": "xs", "align": "end", "flex": 2, "weight": if bold { "bold" } else { "regular" } },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_render() {
+        let message = render(&fixtures::report());
+        assert_eq!(message["type"], "flex");
+        assert!(message["altText"].as_str().unwrap().contains("今月の予測"));
+        // 合計 4 行 + 区切り線 + 見出し + ランキング 3 行
+        assert_eq!(message["contents"]["body"]["contents"].as_array().unwrap().len(), 9);
+    }
+}
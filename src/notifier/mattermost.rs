@@ -0,0 +1,96 @@
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::{post_json_with_retry, Notifier};
+use crate::report::Report;
+
+/// Mattermost Incoming Webhook の設定
+#[derive(Clone, Deserialize)]
+pub struct MattermostConfig {
+    pub webhook_url: String,
+}
+
+impl MattermostConfig {
+    /// 環境変数 `{prefix}MATTERMOST_WEBHOOK_URL` から読み込む。未設定なら `None`
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        env_var(prefix, "MATTERMOST_WEBHOOK_URL").map(|webhook_url| Self { webhook_url })
+    }
+}
+
+/// Mattermost Incoming Webhook への通知
+pub struct MattermostNotifier {
+    client: Client,
+    config: MattermostConfig,
+}
+
+impl MattermostNotifier {
+    pub fn new(config: MattermostConfig) -> Self {
+        Self { client: Client::new(), config }
+    }
+}
+
+#[async_trait]
+impl Notifier for MattermostNotifier {
+    fn name(&self) -> &'static str {
+        "mattermost"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        post_json_with_retry(&self.client, &self.config.webhook_url, &self.preview(report)?).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({ "text": render(report)? }))
+    }
+}
+
+/// 日次レポートを Markdown に変換する。ランキングは表にする
+fn render(report: &Report) -> Result<String, MyError> {
+    let mut text = String::new();
+    writeln!(text, "#### {}", report.title())?;
+    for alert in &report.alerts {
+        writeln!(text, "- :warning: {alert}")?;
+    }
+    writeln!(text)?;
+    writeln!(text, "| | 料金 |")?;
+    writeln!(text, "|:--|--:|")?;
+    for (label, cost) in report.totals() {
+        writeln!(text, "| {label} | {cost} |")?;
+    }
+    writeln!(text)?;
+    writeln!(text, "**{}**", report.ranking_title())?;
+    writeln!(text)?;
+    writeln!(text, "| # | サービス | 料金 |")?;
+    writeln!(text, "|--:|:--|--:|")?;
+    for (i, service) in report.ranking().enumerate() {
+        writeln!(text, "| {} | {} | {} |", report.rank_label(i), escape_table_cell(&service.label()), report.format_cost(service.amount))?;
+    }
+    if let Some(others) = report.others() {
+        writeln!(text, "|  | {} | {} |", escape_table_cell(&others.name), report.format_cost(others.amount))?;
+    }
+    Ok(text)
+}
+
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_render() {
+        let text = render(&fixtures::report()).unwrap();
+        assert!(text.contains("| 1. | Amazon Elastic Compute Cloud - Compute | 1200円($8) |"));
+        assert!(!text.contains(":warning:"));
+    }
+}
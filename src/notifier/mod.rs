@@ -0,0 +1,360 @@
+pub mod chatwork;
+pub mod discord;
+pub mod email;
+pub mod google_chat;
+pub mod line;
+pub mod mattermost;
+pub mod ntfy;
+pub mod opsgenie;
+pub mod pagerduty;
+pub mod pushover;
+pub mod slack;
+pub mod sns;
+pub mod teams;
+pub mod webhook;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use futures::future::join_all;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::MyError;
+use crate::config::Config;
+use crate::notifier::chatwork::{ChatworkConfig, ChatworkNotifier};
+use crate::notifier::discord::{DiscordConfig, DiscordNotifier};
+use crate::notifier::email::{EmailConfig, EmailNotifier};
+use crate::notifier::google_chat::{GoogleChatConfig, GoogleChatNotifier};
+use crate::notifier::line::{LineConfig, LineNotifier};
+use crate::notifier::mattermost::{MattermostConfig, MattermostNotifier};
+use crate::notifier::ntfy::{NtfyConfig, NtfyNotifier};
+use crate::notifier::opsgenie::OpsgenieNotifier;
+use crate::notifier::pagerduty::PagerDutyNotifier;
+use crate::notifier::pushover::{PushoverConfig, PushoverNotifier};
+use crate::notifier::slack::bot::{SlackBotConfig, SlackBotNotifier};
+use crate::notifier::slack::{SlackConfig, SlackNotifier};
+use crate::notifier::sns::{SnsConfig, SnsNotifier};
+use crate::notifier::teams::{TeamsConfig, TeamsNotifier};
+use crate::notifier::webhook::{WebhookConfig, WebhookNotifier};
+use crate::report::{Report, Severity};
+
+/// レポートの通知先
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// ログに出すチャネル名
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, report: &Report) -> Result<(), MyError>;
+
+    /// 送信する内容。ドライランで送信せずに確認するために使う
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(Value::String(report.to_text()?))
+    }
+}
+
+/// 通知チャネルごとの設定。未設定のチャネルには送らない
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChannelsConfig {
+    pub slack: Option<SlackConfig>,
+    pub slack_bot: Option<SlackBotConfig>,
+    pub line: Option<LineConfig>,
+    pub discord: Option<DiscordConfig>,
+    pub teams: Option<TeamsConfig>,
+    pub email: Option<EmailConfig>,
+    pub sns: Option<SnsConfig>,
+    pub chatwork: Option<ChatworkConfig>,
+    pub google_chat: Option<GoogleChatConfig>,
+    pub webhook: Option<WebhookConfig>,
+    pub mattermost: Option<MattermostConfig>,
+    pub ntfy: Option<NtfyConfig>,
+    pub pushover: Option<PushoverConfig>,
+}
+
+impl ChannelsConfig {
+    /// `{prefix}` 付きの環境変数から読み込む
+    pub fn from_env(prefix: &str) -> Result<Self, MyError> {
+        Ok(Self {
+            slack: SlackConfig::from_env(prefix)?,
+            slack_bot: SlackBotConfig::from_env(prefix)?,
+            line: LineConfig::from_env(prefix)?,
+            discord: DiscordConfig::from_env(prefix),
+            teams: TeamsConfig::from_env(prefix),
+            email: EmailConfig::from_env(prefix)?,
+            sns: SnsConfig::from_env(prefix),
+            chatwork: ChatworkConfig::from_env(prefix)?,
+            google_chat: GoogleChatConfig::from_env(prefix),
+            webhook: WebhookConfig::from_env(prefix)?,
+            mattermost: MattermostConfig::from_env(prefix),
+            ntfy: NtfyConfig::from_env(prefix),
+            pushover: PushoverConfig::from_env(prefix)?,
+        })
+    }
+
+    /// `overrides` で設定されているチャネルを置き換える
+    pub fn merge(&mut self, overrides: ChannelsConfig) {
+        self.slack = overrides.slack.or(self.slack.take());
+        self.slack_bot = overrides.slack_bot.or(self.slack_bot.take());
+        self.line = overrides.line.or(self.line.take());
+        self.discord = overrides.discord.or(self.discord.take());
+        self.teams = overrides.teams.or(self.teams.take());
+        self.email = overrides.email.or(self.email.take());
+        self.sns = overrides.sns.or(self.sns.take());
+        self.chatwork = overrides.chatwork.or(self.chatwork.take());
+        self.google_chat = overrides.google_chat.or(self.google_chat.take());
+        self.webhook = overrides.webhook.or(self.webhook.take());
+        self.mattermost = overrides.mattermost.or(self.mattermost.take());
+        self.ntfy = overrides.ntfy.or(self.ntfy.take());
+        self.pushover = overrides.pushover.or(self.pushover.take());
+    }
+
+    fn uses_aws(&self) -> bool {
+        self.email.is_some() || self.sns.is_some()
+    }
+}
+
+/// 重要度ごとに振り分けて送信する通知先の一覧
+#[derive(Default)]
+pub struct NotifierRegistry {
+    routes: Vec<Route>,
+    /// インシデント管理ツールへの通知先。重要度に関係なく毎回渡し、起票するかどうかは各通知先が判断する
+    incident_notifiers: Vec<Box<dyn Notifier>>,
+}
+
+struct Route {
+    severity: Severity,
+    notifier: Box<dyn Notifier>,
+}
+
+/// 1 チャネル分の送信結果
+pub struct Delivery {
+    pub channel: &'static str,
+    pub result: Result<(), MyError>,
+}
+
+/// 1 チャネル分の送信内容
+pub struct Preview {
+    pub channel: &'static str,
+    pub payload: Result<Value, MyError>,
+}
+
+impl NotifierRegistry {
+    /// 設定されている通知先をすべて登録する。
+    /// `channels` は通常の日次レポート用、`alert_channels` はアラート用の通知先になる
+    pub async fn from_config(config: &Config) -> Result<Self, MyError> {
+        let sdk_config = if config.channels.uses_aws() || config.alert_channels.uses_aws() {
+            Some(aws_config::load_from_env().await)
+        } else {
+            None
+        };
+        let mut registry = Self::default();
+        registry.register_channels(Severity::Normal, &config.channels, sdk_config.as_ref()).await?;
+        registry.register_channels(Severity::Alert, &config.alert_channels, sdk_config.as_ref()).await?;
+        if let (Some(pagerduty), Some(budget)) = (&config.pagerduty, config.monthly_budget) {
+            registry.register_incident(PagerDutyNotifier::from_config(pagerduty, budget));
+        }
+        if let Some(opsgenie) = &config.opsgenie {
+            registry.register_incident(OpsgenieNotifier::from_config(opsgenie));
+        }
+        Ok(registry)
+    }
+
+    async fn register_channels(&mut self, severity: Severity, channels: &ChannelsConfig, sdk_config: Option<&SdkConfig>) -> Result<(), MyError> {
+        if let Some(slack) = &channels.slack {
+            self.register(severity, SlackNotifier::from_config(slack).await?);
+        }
+        if let Some(slack_bot) = &channels.slack_bot {
+            self.register(severity, SlackBotNotifier::from_config(slack_bot).await?);
+        }
+        if let Some(line) = &channels.line {
+            self.register(severity, LineNotifier::from_config(line).await?);
+        }
+        if let Some(discord) = &channels.discord {
+            self.register(severity, DiscordNotifier::from_config(discord).await?);
+        }
+        if let Some(teams) = &channels.teams {
+            self.register(severity, TeamsNotifier::new(teams.clone()));
+        }
+        if let (Some(email), Some(sdk_config)) = (&channels.email, sdk_config) {
+            self.register(severity, EmailNotifier::new(sdk_config, email.clone()));
+        }
+        if let (Some(sns), Some(sdk_config)) = (&channels.sns, sdk_config) {
+            self.register(severity, SnsNotifier::new(sdk_config, sns.clone()));
+        }
+        if let Some(chatwork) = &channels.chatwork {
+            self.register(severity, ChatworkNotifier::from_config(chatwork).await?);
+        }
+        if let Some(google_chat) = &channels.google_chat {
+            self.register(severity, GoogleChatNotifier::new(google_chat.clone()));
+        }
+        if let Some(webhook) = &channels.webhook {
+            self.register(severity, WebhookNotifier::from_config(webhook));
+        }
+        if let Some(mattermost) = &channels.mattermost {
+            self.register(severity, MattermostNotifier::new(mattermost.clone()));
+        }
+        if let Some(ntfy) = &channels.ntfy {
+            self.register(severity, NtfyNotifier::from_config(ntfy));
+        }
+        if let Some(pushover) = &channels.pushover {
+            self.register(severity, PushoverNotifier::from_config(pushover));
+        }
+        Ok(())
+    }
+
+    pub fn register(&mut self, severity: Severity, notifier: impl Notifier + 'static) {
+        self.routes.push(Route { severity, notifier: Box::new(notifier) });
+    }
+
+    pub fn register_incident(&mut self, notifier: impl Notifier + 'static) {
+        self.incident_notifiers.push(Box::new(notifier));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty() && self.incident_notifiers.is_empty()
+    }
+
+    /// レポートの重要度に対応する通知先。アラート用の通知先が未設定なら通常の通知先に送る
+    fn targets(&self, severity: Severity) -> Vec<&dyn Notifier> {
+        let has_route = self.routes.iter().any(|route| route.severity == severity);
+        let severity = if has_route { severity } else { Severity::Normal };
+        self.routes.iter()
+            .filter(|route| route.severity == severity)
+            .map(|route| route.notifier.as_ref())
+            .chain(self.incident_notifiers.iter().map(|notifier| notifier.as_ref()))
+            .collect()
+    }
+
+    /// 対象の通知先へ並行して送信する。失敗したチャネルがあっても他のチャネルへの送信は続ける
+    pub async fn send_all(&self, report: &Report) -> Vec<Delivery> {
+        join_all(self.targets(report.severity()).into_iter().map(|notifier| async move {
+            Delivery { channel: notifier.name(), result: notifier.send(report).await }
+        })).await
+    }
+
+    /// 対象の通知先へ送る内容を、送信せずに返す
+    pub fn preview_all(&self, report: &Report) -> Vec<Preview> {
+        self.targets(report.severity()).into_iter()
+            .map(|notifier| Preview { channel: notifier.name(), payload: notifier.preview(report) })
+            .collect()
+    }
+}
+
+/// 5xx 応答時の最大リトライ回数
+const MAX_RETRIES: u32 = 3;
+
+/// JSON を POST し、5xx・通信エラーの場合は指数バックオフでリトライする
+pub(crate) async fn post_json_with_retry<T: Serialize + ?Sized>(client: &Client, url: &str, body: &T) -> Result<Response, MyError> {
+    send_with_retry(client.post(url).json(body), url).await
+}
+
+/// リクエストを送信し、5xx・通信エラーの場合は指数バックオフでリトライする
+pub(crate) async fn send_with_retry(request: RequestBuilder, url: &str) -> Result<Response, MyError> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let cloned = request.try_clone().ok_or("ストリーミングボディのリクエストはリトライできません")?;
+        match cloned.send().await {
+            Ok(response) if response.status().is_server_error() && attempt <= MAX_RETRIES => {
+                println!("{} から {} が返されたためリトライします ({attempt}/{MAX_RETRIES})", url_host(url), response.status());
+            }
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                return Err(format!("{} への送信に失敗しました: {status} {text}", url_host(url)).into());
+            }
+            Err(e) if attempt <= MAX_RETRIES => {
+                println!("{} への送信でエラーが発生したためリトライします ({attempt}/{MAX_RETRIES}): {e}", url_host(url));
+            }
+            Err(e) => return Err(e.into()),
+        }
+        tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+    }
+}
+
+/// Webhook URL はシークレットなのでログにはホスト名だけを出す
+fn url_host(url: &str) -> &str {
+    url.split("://").nth(1).and_then(|rest| rest.split('/').next()).unwrap_or("webhook")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::report::fixtures;
+
+    struct CountingNotifier {
+        name: &'static str,
+        fail: bool,
+        sent: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Notifier for CountingNotifier {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn send(&self, _report: &Report) -> Result<(), MyError> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            if self.fail { Err("failed".into()) } else { Ok(()) }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_all_continues_after_failure() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let mut registry = NotifierRegistry::default();
+        registry.register(Severity::Normal, CountingNotifier { name: "a", fail: true, sent: sent.clone() });
+        registry.register(Severity::Normal, CountingNotifier { name: "b", fail: false, sent: sent.clone() });
+
+        let deliveries = registry.send_all(&fixtures::report()).await;
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+        assert!(deliveries[0].result.is_err());
+        assert!(deliveries[1].result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_all_routes_by_severity() {
+        let normal = Arc::new(AtomicUsize::new(0));
+        let alert = Arc::new(AtomicUsize::new(0));
+        let mut registry = NotifierRegistry::default();
+        registry.register(Severity::Normal, CountingNotifier { name: "normal", fail: false, sent: normal.clone() });
+
+        // アラート用の通知先がなければ通常の通知先に送る
+        let mut report = fixtures::report();
+        report.alerts.push("threshold".to_string());
+        registry.send_all(&report).await;
+        assert_eq!(normal.load(Ordering::SeqCst), 1);
+
+        registry.register(Severity::Alert, CountingNotifier { name: "alert", fail: false, sent: alert.clone() });
+        registry.send_all(&report).await;
+        registry.send_all(&fixtures::report()).await;
+        assert_eq!(normal.load(Ordering::SeqCst), 2);
+        assert_eq!(alert.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_preview_all_does_not_send() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let mut registry = NotifierRegistry::default();
+        registry.register(Severity::Normal, CountingNotifier { name: "a", fail: false, sent: sent.clone() });
+
+        let previews = registry.preview_all(&fixtures::report());
+        assert_eq!(sent.load(Ordering::SeqCst), 0);
+        assert_eq!(previews[0].channel, "a");
+        assert!(previews[0].payload.as_ref().unwrap().as_str().unwrap().contains("前々日料金"));
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("https://hooks.slack.com/services/T000/B000/XXXX"), "hooks.slack.com");
+        assert_eq!(url_host("invalid"), "webhook");
+    }
+}
@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::{Report, Severity};
+
+const DEFAULT_SERVER: &str = "https://ntfy.sh";
+
+/// ntfy の設定
+#[derive(Clone, Deserialize)]
+pub struct NtfyConfig {
+    pub topic: String,
+    #[serde(default = "default_server")]
+    pub server: String,
+    /// アクセス制御されたトピック用のトークン
+    pub access_token: Option<String>,
+}
+
+fn default_server() -> String {
+    DEFAULT_SERVER.to_string()
+}
+
+impl NtfyConfig {
+    /// 環境変数 `{prefix}NTFY_TOPIC`・`{prefix}NTFY_SERVER` (既定 https://ntfy.sh)・`{prefix}NTFY_ACCESS_TOKEN` から読み込む。
+    /// トピックが未設定なら `None`
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        Some(Self {
+            topic: env_var(prefix, "NTFY_TOPIC")?,
+            server: env_var(prefix, "NTFY_SERVER").unwrap_or_else(default_server),
+            access_token: env_var(prefix, "NTFY_ACCESS_TOKEN"),
+        })
+    }
+}
+
+/// ntfy のトピックへのプッシュ通知
+pub struct NtfyNotifier {
+    client: Client,
+    server: String,
+    topic: String,
+    /// アクセス制御されたトピック用のトークン
+    access_token: Option<String>,
+}
+
+impl NtfyNotifier {
+    pub fn new(server: impl Into<String>, topic: impl Into<String>, access_token: Option<String>) -> Self {
+        Self { client: Client::new(), server: server.into(), topic: topic.into(), access_token }
+    }
+
+    pub fn from_config(config: &NtfyConfig) -> Self {
+        Self::new(&config.server, &config.topic, config.access_token.clone())
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let url = format!("{}/{}", self.server.trim_end_matches('/'), self.topic);
+        let (priority, tags) = match report.severity() {
+            Severity::Normal => ("default", "moneybag"),
+            Severity::Alert => ("high", "warning,moneybag"),
+        };
+        let mut request = self.client.post(&url)
+            // ヘッダーは ASCII のみなので件名は英語にする
+            .header("Title", report.prefixed("AWS billing report"))
+            .header("Priority", priority)
+            .header("Tags", tags)
+            .body(report.headline());
+        if let Some(token) = &self.access_token {
+            request = request.bearer_auth(token);
+        }
+        send_with_retry(request, &url).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({ "title": report.prefixed("AWS billing report"), "message": report.headline() }))
+    }
+}
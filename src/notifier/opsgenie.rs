@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use chrono::Datelike;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::{env_required, env_var};
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::Report;
+
+const DEFAULT_API_URL: &str = "https://api.opsgenie.com";
+
+/// Opsgenie の設定
+#[derive(Clone, Deserialize)]
+pub struct OpsgenieConfig {
+    pub api_key: String,
+    /// 今月の予測の閾値 (USD)
+    pub threshold: f64,
+    /// EU リージョンの場合のみ指定する
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+}
+
+fn default_api_url() -> String {
+    DEFAULT_API_URL.to_string()
+}
+
+impl OpsgenieConfig {
+    /// 環境変数 `OPSGENIE_API_KEY`・`OPSGENIE_THRESHOLD_USD`・`OPSGENIE_API_URL` (EU リージョンの場合のみ) から読み込む。
+    /// API キーが未設定なら `None`
+    pub fn from_env() -> Result<Option<Self>, MyError> {
+        let Some(api_key) = env_var("", "OPSGENIE_API_KEY") else {
+            return Ok(None);
+        };
+        let threshold = env_required("", "OPSGENIE_THRESHOLD_USD", "OPSGENIE_API_KEY")?
+            .parse::<f64>()
+            .map_err(|e| format!("設定エラー: OPSGENIE_THRESHOLD_USD の値が不正です: {e}"))?;
+        Ok(Some(Self {
+            api_key,
+            threshold,
+            api_url: env_var("", "OPSGENIE_API_URL").unwrap_or_else(default_api_url),
+        }))
+    }
+}
+
+/// 今月の予測が閾値を超えたときに Opsgenie のアラートを作成する。
+/// 優先度は閾値からの超過率で決める
+pub struct OpsgenieNotifier {
+    client: Client,
+    api_url: String,
+    api_key: String,
+    /// 今月の予測の閾値 (USD)
+    threshold: f64,
+}
+
+impl OpsgenieNotifier {
+    pub fn new(api_url: impl Into<String>, api_key: impl Into<String>, threshold: f64) -> Self {
+        Self { client: Client::new(), api_url: api_url.into(), api_key: api_key.into(), threshold }
+    }
+
+    pub fn from_config(config: &OpsgenieConfig) -> Self {
+        Self::new(&config.api_url, &config.api_key, config.threshold)
+    }
+
+    /// 閾値からの超過率 (%)。超えていなければ `None`
+    fn overrun_percent(&self, report: &Report) -> Option<f64> {
+        (report.forecast > self.threshold).then(|| (report.forecast / self.threshold - 1.0) * 100.0)
+    }
+
+    fn alert(&self, report: &Report, overrun: f64) -> Value {
+        let today = chrono::Utc::now().date_naive();
+        json!({
+            "message": format!("AWS 料金の今月の予測が閾値を {overrun:.1}% 超過しています"),
+            // 同じ月のアラートは 1 件にまとめる
+            "alias": format!("billing-forecast-{}-{:02}", today.year(), today.month()),
+            "description": format!(
+                "今月の予測: {}\n閾値: {}\n現時点料金: {}",
+                report.format_cost(report.forecast),
+                report.format_cost(self.threshold),
+                report.format_cost(report.monthly_cost),
+            ),
+            "priority": priority(overrun),
+            "source": "billing_notification",
+            "details": {
+                "forecast_usd": report.forecast.to_string(),
+                "threshold_usd": self.threshold.to_string(),
+            },
+        })
+    }
+}
+
+/// 超過率から優先度を決める
+fn priority(overrun_percent: f64) -> &'static str {
+    match overrun_percent {
+        p if p >= 50.0 => "P1",
+        p if p >= 25.0 => "P2",
+        p if p >= 10.0 => "P3",
+        p if p >= 5.0 => "P4",
+        _ => "P5",
+    }
+}
+
+#[async_trait]
+impl Notifier for OpsgenieNotifier {
+    fn name(&self) -> &'static str {
+        "opsgenie"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let Some(overrun) = self.overrun_percent(report) else {
+            return Ok(());
+        };
+        let url = format!("{}/v2/alerts", self.api_url.trim_end_matches('/'));
+        let request = self.client.post(&url)
+            .header("Authorization", format!("GenieKey {}", self.api_key))
+            .json(&self.alert(report, overrun));
+        send_with_retry(request, &url).await?;
+        Ok(())
+    }
+
+    /// アラートを作成しない場合は `null`
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(self.overrun_percent(report).map(|overrun| self.alert(report, overrun)).unwrap_or(Value::Null))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(priority(80.0), "P1");
+        assert_eq!(priority(30.0), "P2");
+        assert_eq!(priority(10.0), "P3");
+        assert_eq!(priority(5.0), "P4");
+        assert_eq!(priority(0.1), "P5");
+    }
+
+    #[test]
+    fn test_overrun_percent() {
+        // fixtures の予測は 310 USD
+        let report = fixtures::report();
+        assert_eq!(OpsgenieNotifier::new(DEFAULT_API_URL, "key", 400.0).overrun_percent(&report), None);
+        let overrun = OpsgenieNotifier::new(DEFAULT_API_URL, "key", 200.0).overrun_percent(&report).unwrap();
+        assert!((overrun - 55.0).abs() < 1e-9);
+    }
+}
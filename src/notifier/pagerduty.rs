@@ -0,0 +1,128 @@
+use async_trait::async_trait;
+use chrono::Datelike;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::{env_parse, env_var};
+use crate::notifier::{post_json_with_retry, Notifier};
+use crate::report::Report;
+
+const ENQUEUE_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// PagerDuty の設定
+#[derive(Clone, Deserialize)]
+pub struct PagerDutyConfig {
+    pub routing_key: String,
+    /// 予算超過とみなす割合 (%)
+    #[serde(default)]
+    pub overrun_percent: f64,
+}
+
+impl PagerDutyConfig {
+    /// 環境変数 `PAGERDUTY_ROUTING_KEY` と `PAGERDUTY_BUDGET_OVERRUN_PERCENT` (既定 0) から読み込む。
+    /// ルーティングキーが未設定なら `None`
+    pub fn from_env() -> Result<Option<Self>, MyError> {
+        let Some(routing_key) = env_var("", "PAGERDUTY_ROUTING_KEY") else {
+            return Ok(None);
+        };
+        let overrun_percent = env_parse("", "PAGERDUTY_BUDGET_OVERRUN_PERCENT")?.unwrap_or(0.0);
+        Ok(Some(Self { routing_key, overrun_percent }))
+    }
+}
+
+/// 今月の予測が予算を一定割合以上超えたときに PagerDuty (Events API v2) でインシデントを起票する。
+/// 日次レポートとは別に、条件を満たしたときだけ送信する
+pub struct PagerDutyNotifier {
+    client: Client,
+    routing_key: String,
+    /// 月の予算 (USD)
+    budget: f64,
+    /// 予算超過とみなす割合 (%)
+    overrun_percent: f64,
+}
+
+impl PagerDutyNotifier {
+    pub fn new(routing_key: impl Into<String>, budget: f64, overrun_percent: f64) -> Self {
+        Self { client: Client::new(), routing_key: routing_key.into(), budget, overrun_percent }
+    }
+
+    /// 予算は `Config::monthly_budget` を使う
+    pub fn from_config(config: &PagerDutyConfig, budget: f64) -> Self {
+        Self::new(&config.routing_key, budget, config.overrun_percent)
+    }
+
+    /// 予測が予算の (100 + N)% を超えているか
+    fn is_overrun(&self, report: &Report) -> bool {
+        report.forecast > self.budget * (1.0 + self.overrun_percent / 100.0)
+    }
+
+    fn event(&self, report: &Report) -> Value {
+        let today = chrono::Utc::now().date_naive();
+        let overrun = (report.forecast / self.budget - 1.0) * 100.0;
+        json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            // 同じ月のインシデントは 1 件にまとめる
+            "dedup_key": format!("billing-forecast-{}-{:02}", today.year(), today.month()),
+            "payload": {
+                "summary": format!(
+                    "今月の予測 {} が予算 {} を {overrun:.1}% 超過しています",
+                    report.format_cost(report.forecast),
+                    report.format_cost(self.budget),
+                ),
+                "source": "billing_notification",
+                "severity": "error",
+                "custom_details": {
+                    "forecast_usd": report.forecast,
+                    "budget_usd": self.budget,
+                    "month_to_date_usd": report.monthly_cost,
+                    "overrun_percent": overrun,
+                },
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for PagerDutyNotifier {
+    fn name(&self) -> &'static str {
+        "pagerduty"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        if !self.is_overrun(report) {
+            return Ok(());
+        }
+        post_json_with_retry(&self.client, ENQUEUE_URL, &self.event(report)).await?;
+        Ok(())
+    }
+
+    /// 起票しない場合は `null`
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(if self.is_overrun(report) { self.event(report) } else { Value::Null })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_is_overrun() {
+        // fixtures の予測は 310 USD
+        let report = fixtures::report();
+        assert!(PagerDutyNotifier::new("key", 300.0, 0.0).is_overrun(&report));
+        assert!(!PagerDutyNotifier::new("key", 300.0, 5.0).is_overrun(&report));
+        assert!(!PagerDutyNotifier::new("key", 400.0, 0.0).is_overrun(&report));
+    }
+
+    #[test]
+    fn test_event() {
+        let event = PagerDutyNotifier::new("key", 300.0, 0.0).event(&fixtures::report());
+        assert_eq!(event["event_action"], "trigger");
+        assert!(event["dedup_key"].as_str().unwrap().starts_with("billing-forecast-"));
+    }
+}
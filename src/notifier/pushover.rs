@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::{env_required, env_var};
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::{Report, Severity};
+
+const MESSAGES_URL: &str = "https://api.pushover.net/1/messages.json";
+
+/// Pushover の設定
+#[derive(Clone, Deserialize)]
+pub struct PushoverConfig {
+    pub user_key: String,
+    pub app_token: String,
+}
+
+impl PushoverConfig {
+    /// 環境変数 `{prefix}PUSHOVER_USER_KEY` と `{prefix}PUSHOVER_APP_TOKEN` から読み込む。ユーザーキーが未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let Some(user_key) = env_var(prefix, "PUSHOVER_USER_KEY") else {
+            return Ok(None);
+        };
+        Ok(Some(Self { user_key, app_token: env_required(prefix, "PUSHOVER_APP_TOKEN", "PUSHOVER_USER_KEY")? }))
+    }
+}
+
+/// Pushover によるプッシュ通知
+pub struct PushoverNotifier {
+    client: Client,
+    app_token: String,
+    user_key: String,
+}
+
+impl PushoverNotifier {
+    pub fn new(app_token: impl Into<String>, user_key: impl Into<String>) -> Self {
+        Self { client: Client::new(), app_token: app_token.into(), user_key: user_key.into() }
+    }
+
+    pub fn from_config(config: &PushoverConfig) -> Self {
+        Self::new(&config.app_token, &config.user_key)
+    }
+}
+
+#[async_trait]
+impl Notifier for PushoverNotifier {
+    fn name(&self) -> &'static str {
+        "pushover"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let priority = match report.severity() {
+            Severity::Normal => "0",
+            Severity::Alert => "1",
+        };
+        let request = self.client.post(MESSAGES_URL).form(&[
+            ("token", self.app_token.as_str()),
+            ("user", self.user_key.as_str()),
+            ("title", &report.title()),
+            ("message", &report.headline()),
+            ("priority", priority),
+        ]);
+        send_with_retry(request, MESSAGES_URL).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({ "title": report.title(), "message": report.headline() }))
+    }
+}
@@ -0,0 +1,90 @@
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::report::{format_service_costs, Report};
+
+/// 「詳細を表示」ボタンの action_id。押されると `slack_app::interaction` が全サービスの内訳を返す
+pub const SHOW_DETAILS_ACTION_ID: &str = "show_details";
+
+/// 日次レポートを Block Kit のブロック列に変換する
+pub fn render(report: &Report, details_button: bool) -> Result<Vec<Value>, MyError> {
+    let ranking = format_service_costs(&report.numbered_ranking(), &report.exchange, usize::MAX, true)?;
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": { "type": "plain_text", "text": report.title() },
+    })];
+    if !report.alerts.is_empty() {
+        let alerts = report.alerts.iter().map(|alert| format!("• {alert}")).collect::<Vec<_>>().join("\n");
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("*■⚠️ アラート: 閾値を超えています*\n{alerts}") },
+        }));
+    }
+    blocks.extend([
+        json!({
+            "type": "section",
+            "fields": report.totals().iter().map(|(label, cost)| field(label, cost)).collect::<Vec<_>>(),
+        }),
+    ]);
+    if let Some(composition) = report.composition_text() {
+        blocks.push(json!({
+            "type": "context",
+            "elements": [{ "type": "mrkdwn", "text": format!("現時点料金の内訳: {composition}") }],
+        }));
+    }
+    blocks.extend([
+        json!({ "type": "divider" }),
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("*{}*\n{ranking}", report.ranking_title()) },
+        }),
+    ]);
+    for breakdown in &report.breakdowns {
+        let items = format_service_costs(&breakdown.items, &report.exchange, usize::MAX, false)?;
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("*■{}*\n{items}", breakdown.title) },
+        }));
+    }
+    if details_button && report.others().is_some() {
+        blocks.push(json!({
+            "type": "actions",
+            "elements": [{
+                "type": "button",
+                "text": { "type": "plain_text", "text": "詳細を表示" },
+                "action_id": SHOW_DETAILS_ACTION_ID,
+                "value": SHOW_DETAILS_ACTION_ID,
+            }],
+        }));
+    }
+    Ok(blocks)
+}
+
+fn field(label: &str, value: &str) -> Value {
+    json!({ "type": "mrkdwn", "text": format!("*{label}*\n{value}") })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_render() {
+        let blocks = render(&fixtures::report(), false).unwrap();
+        assert_eq!(blocks[0]["type"], "header");
+        assert_eq!(blocks[1]["fields"].as_array().unwrap().len(), 4);
+        assert!(blocks[3]["text"]["text"].as_str().unwrap().contains("AWS Lambda"));
+    }
+
+    #[test]
+    fn test_render_details_button() {
+        let mut report = fixtures::report();
+        // 全件がランキングに収まっているときはボタンを出さない
+        assert_eq!(render(&report, true).unwrap().len(), 4);
+
+        report.display_count = 1;
+        let blocks = render(&report, true).unwrap();
+        assert_eq!(blocks[4]["elements"][0]["action_id"], SHOW_DETAILS_ACTION_ID);
+    }
+}
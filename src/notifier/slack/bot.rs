@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::{env_required, env_var};
+use crate::notifier::slack::mention_text;
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::{Report, Severity};
+use crate::secrets::Secret;
+
+/// Slack の Bot トークンによる投稿の設定
+#[derive(Clone, Deserialize)]
+pub struct SlackBotConfig {
+    pub token: Secret,
+    pub channel: String,
+    /// アラート時に親メッセージの先頭に付けるメンション。書き方は Incoming Webhook と同じ
+    pub mention: Option<String>,
+}
+
+impl SlackBotConfig {
+    /// 環境変数 `{prefix}SLACK_BOT_TOKEN` (または `{prefix}SLACK_BOT_TOKEN_SECRET_ID`)・`{prefix}SLACK_CHANNEL`・`{prefix}SLACK_MENTION` から読み込む。
+    /// トークンが未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let Some(token) = Secret::from_env(prefix, "SLACK_BOT_TOKEN") else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            token,
+            channel: env_required(prefix, "SLACK_CHANNEL", "SLACK_BOT_TOKEN")?,
+            mention: env_var(prefix, "SLACK_MENTION"),
+        }))
+    }
+}
+
+/// Slack の Bot トークンによる `chat.postMessage` での通知。
+/// 概要を親メッセージとして投稿し、サービス別の内訳はスレッドに返信する
+pub struct SlackBotNotifier {
+    client: Client,
+    token: String,
+    config: SlackBotConfig,
+}
+
+impl SlackBotNotifier {
+    /// トークンがシークレットで指定されていれば取得して生成する
+    pub async fn from_config(config: &SlackBotConfig) -> Result<Self, MyError> {
+        Ok(Self { client: Client::new(), token: config.token.resolve().await?, config: config.clone() })
+    }
+
+    /// 親メッセージの本文。アラート時はメンションを付ける
+    fn summary(&self, report: &Report) -> Result<String, MyError> {
+        Ok(match self.config.mention.as_deref().filter(|_| report.severity() == Severity::Alert) {
+            Some(mention) => format!("{}\n{}", mention_text(mention), report.summary_text()?),
+            None => report.summary_text()?,
+        })
+    }
+
+    /// Web API を呼び出す。HTTP 200 でも `ok: false` ならエラーにする
+    async fn call(&self, method: &str, body: &Value) -> Result<Value, MyError> {
+        let url = format!("https://slack.com/api/{method}");
+        let request = self.client.post(&url).bearer_auth(&self.token).json(body);
+        let response: Value = send_with_retry(request, &url).await?.json().await?;
+        if response["ok"].as_bool() != Some(true) {
+            return Err(format!("Slack API {method} がエラーを返しました: {}", response["error"]).into());
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackBotNotifier {
+    fn name(&self) -> &'static str {
+        "slack_bot"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let parent = self.call("chat.postMessage", &json!({ "channel": self.config.channel, "text": self.summary(report)? })).await?;
+        let thread_ts = parent["ts"].as_str().ok_or("chat.postMessage のレスポンスに ts がありません")?;
+
+        self.call("chat.postMessage", &json!({
+            "channel": self.config.channel,
+            "thread_ts": thread_ts,
+            "text": thread_text(report)?,
+        })).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({ "channel": self.config.channel, "text": self.summary(report)?, "thread": thread_text(report)? }))
+    }
+}
+
+/// スレッドに返信する本文。ランキングで省略したサービスも含めた全サービスの料金と内訳
+fn thread_text(report: &Report) -> Result<String, MyError> {
+    Ok(format!("{}{}", report.full_breakdown_text()?, report.breakdowns_text()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_preview_thread_has_every_service() {
+        let config = SlackBotConfig { token: Secret::Plain("xoxb-test".to_string()), channel: "#billing".to_string(), mention: None };
+        let notifier = SlackBotNotifier { client: Client::new(), token: "xoxb-test".to_string(), config };
+        let report = Report { display_count: 1, ..fixtures::report() };
+
+        let preview = notifier.preview(&report).unwrap();
+        assert!(!preview["text"].as_str().unwrap().contains("AWS Lambda"));
+        let thread = preview["thread"].as_str().unwrap();
+        assert!(thread.contains("■前々日の全サービスの料金"));
+        assert!(thread.contains("AWS Lambda"));
+    }
+}
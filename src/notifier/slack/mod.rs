@@ -0,0 +1,141 @@
+pub mod blocks;
+pub mod bot;
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::{env_flag, env_parse, env_var};
+use crate::notifier::{post_json_with_retry, Notifier};
+use crate::report::{Report, Severity};
+use crate::secrets::Secret;
+
+/// Slack に送るメッセージの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlackFormat {
+    /// コードブロックを含むプレーンテキスト
+    #[default]
+    Text,
+    /// Block Kit
+    Blocks,
+}
+
+impl FromStr for SlackFormat {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "blocks" => Ok(Self::Blocks),
+            other => Err(format!("text か blocks を指定してください: {other}").into()),
+        }
+    }
+}
+
+/// Slack Incoming Webhook の設定
+#[derive(Clone, Deserialize)]
+pub struct SlackConfig {
+    pub webhook_url: Secret,
+    #[serde(default)]
+    pub format: SlackFormat,
+    /// アラート時に先頭に付けるメンション (`<!channel>` や `<@U123>`)。空白で区切って複数指定でき、
+    /// `@channel`・`@here`・`@everyone` は Slack の特殊メンションに置き換える
+    pub mention: Option<String>,
+    /// Block Kit 形式のとき「詳細を表示」ボタンを付けるか
+    #[serde(default)]
+    pub details_button: bool,
+}
+
+impl SlackConfig {
+    /// 環境変数 `{prefix}SLACK_WEBHOOK_URL` (または `{prefix}SLACK_WEBHOOK_URL_SECRET_ID`)・`{prefix}SLACK_FORMAT`・`{prefix}SLACK_MENTION`・
+    /// `{prefix}SLACK_DETAILS_BUTTON` から読み込む。URL が未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let Some(webhook_url) = Secret::from_env(prefix, "SLACK_WEBHOOK_URL") else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            webhook_url,
+            format: env_parse(prefix, "SLACK_FORMAT")?.unwrap_or_default(),
+            mention: env_var(prefix, "SLACK_MENTION"),
+            details_button: env_flag(prefix, "SLACK_DETAILS_BUTTON")?,
+        }))
+    }
+}
+
+/// メンションの設定を本文に書く形にする。`@channel` などはそのままでは通知されない
+pub(crate) fn mention_text(mention: &str) -> String {
+    mention.split_whitespace()
+        .map(|token| match token {
+            "@channel" | "@here" | "@everyone" => format!("<!{}>", &token[1..]),
+            token => token.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Slack Incoming Webhook への通知
+pub struct SlackNotifier {
+    client: Client,
+    webhook_url: String,
+    config: SlackConfig,
+}
+
+impl SlackNotifier {
+    /// Webhook URL がシークレットで指定されていれば取得して生成する
+    pub async fn from_config(config: &SlackConfig) -> Result<Self, MyError> {
+        Ok(Self { client: Client::new(), webhook_url: config.webhook_url.resolve().await?, config: config.clone() })
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        post_json_with_retry(&self.client, &self.webhook_url, &self.preview(report)?).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        let mention = self.config.mention.as_deref().filter(|_| report.severity() == Severity::Alert).map(mention_text);
+        let text = match mention {
+            Some(mention) => format!("{mention}\n{}", report.to_text()?),
+            None => report.to_text()?,
+        };
+        Ok(match self.config.format {
+            SlackFormat::Text => json!({ "text": text }),
+            SlackFormat::Blocks => {
+                let mut blocks = blocks::render(report, self.config.details_button)?;
+                if let Some(mention) = mention {
+                    blocks.insert(0, json!({ "type": "section", "text": { "type": "mrkdwn", "text": mention } }));
+                }
+                json!({ "text": text, "blocks": blocks })
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slack_format() {
+        assert_eq!("blocks".parse::<SlackFormat>().unwrap(), SlackFormat::Blocks);
+        assert_eq!("TEXT".parse::<SlackFormat>().unwrap(), SlackFormat::Text);
+        assert!("markdown".parse::<SlackFormat>().is_err());
+    }
+
+    #[test]
+    fn test_mention_text() {
+        assert_eq!(mention_text("@channel <@U123>"), "<!channel> <@U123>");
+        assert_eq!(mention_text("<!subteam^S123>"), "<!subteam^S123>");
+    }
+}
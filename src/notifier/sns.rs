@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use aws_sdk_sns as sns;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::Notifier;
+use crate::report::Report;
+
+/// SNS の件名は ASCII のみ
+const SUBJECT: &str = "AWS billing report";
+
+/// SNS トピックの設定
+#[derive(Clone, Deserialize)]
+pub struct SnsConfig {
+    pub topic_arn: String,
+}
+
+impl SnsConfig {
+    /// 環境変数 `{prefix}SNS_TOPIC_ARN` から読み込む。未設定なら `None`
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        env_var(prefix, "SNS_TOPIC_ARN").map(|topic_arn| Self { topic_arn })
+    }
+}
+
+/// SNS トピックへの発行。
+/// メール等にはテキスト版を、SQS・Lambda・HTTP(S) のサブスクライバーには構造化 JSON を配信する
+pub struct SnsNotifier {
+    client: sns::Client,
+    config: SnsConfig,
+}
+
+impl SnsNotifier {
+    pub fn new(sdk_config: &SdkConfig, config: SnsConfig) -> Self {
+        Self { client: sns::Client::new(sdk_config), config }
+    }
+}
+
+#[async_trait]
+impl Notifier for SnsNotifier {
+    fn name(&self) -> &'static str {
+        "sns"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        self.client.publish()
+            .topic_arn(&self.config.topic_arn)
+            .subject(report.prefixed(SUBJECT))
+            .message_structure("json")
+            .message(render(report)?)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({ "subject": report.prefixed(SUBJECT), "message": serde_json::from_str::<Value>(&render(report)?)? }))
+    }
+}
+
+/// プロトコルごとのメッセージを持つ SNS の JSON メッセージに変換する
+fn render(report: &Report) -> Result<String, MyError> {
+    let text = report.to_text()?;
+    let structured = serde_json::to_string(report)?;
+    Ok(json!({
+        "default": text,
+        "sqs": structured,
+        "lambda": structured,
+        "http": structured,
+        "https": structured,
+    }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+    use serde_json::Value;
+
+    #[test]
+    fn test_render() {
+        let message: Value = serde_json::from_str(&render(&fixtures::report()).unwrap()).unwrap();
+        assert!(message["default"].as_str().unwrap().contains("前々日料金"));
+        let structured: Value = serde_json::from_str(message["sqs"].as_str().unwrap()).unwrap();
+        assert_eq!(structured["daily_total"], 12.5);
+        assert_eq!(structured["services"][0]["name"], "Amazon Elastic Compute Cloud - Compute");
+    }
+}
@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::MyError;
+use crate::config::env_var;
+use crate::notifier::{post_json_with_retry, Notifier};
+use crate::report::Report;
+
+/// Microsoft Teams Incoming Webhook の設定
+#[derive(Clone, Deserialize)]
+pub struct TeamsConfig {
+    pub webhook_url: String,
+}
+
+impl TeamsConfig {
+    /// 環境変数 `{prefix}TEAMS_WEBHOOK_URL` から読み込む。未設定なら `None`
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        env_var(prefix, "TEAMS_WEBHOOK_URL").map(|webhook_url| Self { webhook_url })
+    }
+}
+
+/// Microsoft Teams Incoming Webhook への通知
+pub struct TeamsNotifier {
+    client: Client,
+    config: TeamsConfig,
+}
+
+impl TeamsNotifier {
+    pub fn new(config: TeamsConfig) -> Self {
+        Self { client: Client::new(), config }
+    }
+}
+
+#[async_trait]
+impl Notifier for TeamsNotifier {
+    fn name(&self) -> &'static str {
+        "teams"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        post_json_with_retry(&self.client, &self.config.webhook_url, &self.preview(report)?).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": render(report),
+            }],
+        }))
+    }
+}
+
+/// 日次レポートを Adaptive Card に変換する。合計は FactSet、ランキングは Table にする
+fn render(report: &Report) -> Value {
+    let facts = report.totals()
+        .iter()
+        .map(|(title, cost)| json!({ "title": title, "value": cost }))
+        .collect::<Vec<_>>();
+
+    let mut rows = vec![table_row("サービス", "料金", true)];
+    rows.extend(report.numbered_ranking().iter().map(|service| table_row(&service.label(), &report.format_cost(service.amount), false)));
+
+    json!({
+        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+        "type": "AdaptiveCard",
+        "version": "1.5",
+        "body": [
+            { "type": "TextBlock", "text": report.title(), "size": "Large", "weight": "Bolder" },
+            { "type": "FactSet", "facts": facts },
+            { "type": "TextBlock", "text": report.ranking_title(), "weight": "Bolder", "separator": true },
+            {
+                "type": "Table",
+                "columns": [{ "width": 3 }, { "width": 2 }],
+                "firstRowAsHeader": true,
+                "rows": rows,
+            },
+        ],
+    })
+}
+
+fn table_row(label: &str, value: &str, header: bool) -> Value {
+    let weight = if header { "Bolder" } else { "Default" };
+    json!({
+        "type": "TableRow",
+        "cells": [
+            { "type": "TableCell", "items": [{ "type": "TextBlock", "text": label, "wrap": true, "weight": weight }] },
+            { "type": "TableCell", "items": [{ "type": "TextBlock", "text": value, "horizontalAlignment": "Right", "weight": weight }] },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    #[test]
+    fn test_render() {
+        let card = render(&fixtures::report());
+        assert_eq!(card["body"][1]["facts"].as_array().unwrap().len(), 4);
+        // ヘッダー行 + ランキング 3 行
+        assert_eq!(card["body"][3]["rows"].as_array().unwrap().len(), 4);
+    }
+}
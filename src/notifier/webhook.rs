@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::MyError;
+use crate::config::{env_required, env_var};
+use crate::notifier::{send_with_retry, Notifier};
+use crate::report::Report;
+
+/// 署名を載せるヘッダー。値は `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// 汎用 Webhook の設定
+#[derive(Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// 署名に使う共有シークレット
+    pub secret: String,
+}
+
+impl WebhookConfig {
+    /// 環境変数 `{prefix}WEBHOOK_URL` と `{prefix}WEBHOOK_SECRET` から読み込む。`{prefix}WEBHOOK_URL` が未設定なら `None`
+    pub fn from_env(prefix: &str) -> Result<Option<Self>, MyError> {
+        let Some(url) = env_var(prefix, "WEBHOOK_URL") else {
+            return Ok(None);
+        };
+        Ok(Some(Self { url, secret: env_required(prefix, "WEBHOOK_SECRET", "WEBHOOK_URL")? }))
+    }
+}
+
+/// 任意の URL に構造化 JSON を POST する汎用 Webhook。
+/// 受信側が検証できるよう、ボディの HMAC-SHA256 を `X-Signature-256` ヘッダーに付与する
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+    secret: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self { client: Client::new(), url: url.into(), secret: secret.into() }
+    }
+
+    pub fn from_config(config: &WebhookConfig) -> Self {
+        Self::new(&config.url, &config.secret)
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, report: &Report) -> Result<(), MyError> {
+        let body = serde_json::to_vec(&self.preview(report)?)?;
+        let signature = sign(&self.secret, &body)?;
+        let request = self.client.post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .body(body);
+        send_with_retry(request, &self.url).await?;
+        Ok(())
+    }
+
+    fn preview(&self, report: &Report) -> Result<Value, MyError> {
+        Ok(json!({
+            "generated_at": chrono::Utc::now().to_rfc3339(),
+            "report": report,
+        }))
+    }
+}
+
+/// `sha256=<hex>` 形式の署名を返す
+fn sign(secret: &str, body: &[u8]) -> Result<String, MyError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| format!("HMAC の鍵が不正です: {e}"))?;
+    mac.update(body);
+    Ok(format!("sha256={}", hex::encode(mac.finalize().into_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 Test Case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?").unwrap(),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        );
+    }
+}
@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::alert::{QuietConfig, SPIKE_HISTORY_DAYS};
+use crate::cloudwatch;
+use crate::config::Config;
+use crate::cost_explorer::{self, CostExplorer, CostExplorerConfig, MoversOrder};
+use crate::cur::{self, CurConfig};
+use crate::report::{extrapolate, Breakdown, BudgetRunway, Comparison, Exchange, ForecastInterval, MOVING_AVERAGE_DAYS, Report, ReportType, Sections, ServiceCost, Spotlight};
+
+/// 初めての請求とみなすために、請求がなかったことを確かめる日数
+const NEW_SERVICE_LOOKBACK_DAYS: u32 = 30;
+
+/// 請求がなくなったとみなすために、請求があったことを確かめる日数
+const DISAPPEARED_SERVICE_LOOKBACK_DAYS: u32 = 7;
+
+/// 請求がなくなったサービスとして載せる、直前の期間の料金の下限 (USD)
+const DISAPPEARED_SERVICE_FLOOR: f64 = 1.0;
+
+/// ランキング 1 位のサービスに添える利用タイプの数
+const TOP_USAGE_TYPE_COUNT: usize = 3;
+
+/// 料金の取得元
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[default]
+    CostExplorer,
+    /// Athena で問い合わせる CUR。`cur` の設定が必要
+    Cur,
+    /// CloudWatch の請求見込み額。合計だけを載せる
+    Cloudwatch,
+}
+
+impl FromStr for ProviderKind {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cost_explorer" => Ok(Self::CostExplorer),
+            "cur" => Ok(Self::Cur),
+            "cloudwatch" => Ok(Self::Cloudwatch),
+            other => Err(format!("cost_explorer・cur・cloudwatch のいずれかを指定してください: {other}").into()),
+        }
+    }
+}
+
+/// 取得元から集めた料金 (USD)。取得元が提供しない値は `None` か空にする
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Costs {
+    /// 集計期間の表示名
+    pub period: String,
+    /// 集計期間の合計料金
+    pub period_total: f64,
+    /// 集計期間の料金がまだ確定していない速報値か
+    pub estimated: bool,
+    /// 集計期間の最終日までの 1 日あたりの移動平均
+    pub moving_average: Option<f64>,
+    /// 集計期間の直前の日ごとの合計料金 (古い順)
+    pub daily_history: Vec<f64>,
+    /// 集計期間の合計料金と比べる基準
+    pub comparisons: Vec<Comparison>,
+    /// 集計期間の料金のうち、設定したコスト配分タグのない分
+    pub untagged_cost: Option<f64>,
+    /// 集計期間の環境 (prod・stg・dev など) 別の料金
+    pub environments: Vec<ServiceCost>,
+    /// 集計期間の直前の 1 日あたりの平均料金
+    pub recent_average: Option<f64>,
+    /// 今月の現時点料金
+    pub monthly_cost: f64,
+    pub last_year_month_to_date: Option<f64>,
+    pub ai_spotlight: Option<Spotlight>,
+    pub forecast: Option<f64>,
+    pub forecast_interval: Option<ForecastInterval>,
+    pub quarter_forecast: Option<f64>,
+    pub year_forecast: Option<f64>,
+    pub last_month_cost: Option<f64>,
+    /// 集計期間のサービス別料金 (降順)
+    pub services: Vec<ServiceCost>,
+    pub breakdowns: Vec<Breakdown>,
+    pub month_composition: Vec<ServiceCost>,
+    pub notice: Option<String>,
+}
+
+impl Costs {
+    /// レポートを組み立てる。予測がなければ予測のセクションを外し、日割りの推定だけを載せる
+    pub fn into_report(self, exchange: Exchange, config: &Config) -> Report {
+        let current_month = config.cost_explorer.current_month(config.today()).ok();
+        Report {
+            report_type: config.report_type,
+            exchange,
+            period: self.period,
+            daily_total: self.period_total,
+            estimated: self.estimated,
+            moving_average: self.moving_average,
+            daily_history: self.daily_history,
+            comparisons: self.comparisons,
+            untagged_cost: self.untagged_cost,
+            environments: self.environments,
+            recent_average: self.recent_average,
+            monthly_cost: self.monthly_cost,
+            last_year_month_to_date: self.last_year_month_to_date,
+            ai_spotlight: self.ai_spotlight,
+            forecast: self.forecast.unwrap_or(0.0),
+            forecast_interval: self.forecast_interval,
+            extrapolation: current_month.filter(|_| config.sections.forecast).map(|month| extrapolate(self.monthly_cost, config.today(), month)),
+            quarter_forecast: self.quarter_forecast,
+            year_forecast: self.year_forecast,
+            last_month_cost: self.last_month_cost,
+            budget_runway: config.monthly_budget
+                .filter(|_| config.report_type != ReportType::Monthly)
+                .zip(current_month)
+                .map(|(budget, month)| BudgetRunway::new(budget, self.monthly_cost, config.today(), month)),
+            budget_outcome: config.monthly_budget.filter(|_| config.report_type == ReportType::Monthly),
+            services: self.services,
+            display_count: config.display_count,
+            ranking_medals: config.ranking_medals,
+            ranking_floor: config.ranking_floor,
+            alerts: Vec::new(),
+            sections: Sections {
+                forecast: config.sections.forecast && self.forecast.is_some(),
+                month_composition: config.sections.month_composition && !self.month_composition.is_empty(),
+                ..config.sections
+            },
+            message_prefix: config.message_prefix(),
+            breakdowns: self.breakdowns,
+            month_composition: self.month_composition,
+            notice: self.notice,
+        }
+    }
+}
+
+/// レポートに載せる料金の取得元
+#[async_trait]
+pub trait CostProvider: Send + Sync {
+    /// ログに出す取得元の名前
+    fn name(&self) -> &'static str;
+
+    async fn fetch(&self, today: NaiveDate) -> Result<Costs, MyError>;
+
+    /// エラーが取得元を使えないことによるものか。`true` なら CloudWatch の請求見込み額に切り替える
+    fn is_unavailable(&self, _e: &MyError) -> bool {
+        false
+    }
+}
+
+/// 設定した取得元を作る
+pub async fn from_config(config: &Config) -> Result<Box<dyn CostProvider>, MyError> {
+    Ok(match config.provider {
+        ProviderKind::CostExplorer => Box::new(CostExplorerProvider::new(config).await),
+        ProviderKind::Cur => Box::new(CurAthenaProvider::new(config)?),
+        ProviderKind::Cloudwatch => Box::new(CloudWatchProvider::new(config)),
+    })
+}
+
+/// Cost Explorer。CUR が設定されていれば、その内訳も加える
+pub struct CostExplorerProvider {
+    cost_explorer: CostExplorer,
+    config: CostExplorerConfig,
+    service_aliases: HashMap<String, String>,
+    service_budgets: HashMap<String, f64>,
+    timezone: Tz,
+    sections: Sections,
+    cur: Option<CurConfig>,
+    /// 直前の平均と比べて通知を省くか
+    quiet: bool,
+    /// 直近の日ごとの料金と比べてスパイクを判定するか
+    spike_detection: bool,
+}
+
+impl CostExplorerProvider {
+    pub async fn new(config: &Config) -> Self {
+        Self {
+            cost_explorer: CostExplorer::new(config.cost_explorer.clone()).await,
+            config: config.cost_explorer.clone(),
+            service_aliases: config.service_aliases.clone(),
+            service_budgets: config.service_budgets.clone(),
+            timezone: config.timezone,
+            sections: config.sections,
+            cur: config.cur.clone(),
+            quiet: config.quiet.is_some(),
+            spike_detection: config.thresholds.spike_sigma.is_some(),
+        }
+    }
+}
+
+#[async_trait]
+impl CostProvider for CostExplorerProvider {
+    fn name(&self) -> &'static str {
+        "Cost Explorer"
+    }
+
+    async fn fetch(&self, today: NaiveDate) -> Result<Costs, MyError> {
+        let cost_explorer = &self.cost_explorer;
+        let cost_and_usage = cost_explorer.fetch_cost_and_usage(today).await?;
+        // 月初はデータが足りず予測を取得できないことがある。そのときは日割りの推定だけを載せる
+        let forecast = cost_explorer.fetch_current_month_cost_forecast(today).await
+            .inspect_err(|e| eprintln!("今月の予測を取得できませんでした: {e}"))
+            .ok();
+
+        let service_forecasts = cost_explorer.fetch_service_forecasts(today, &cost_and_usage.groups, &self.service_aliases).await?;
+        let services: Vec<_> = cost_explorer.to_service_costs(&cost_and_usage.groups, &self.service_aliases).into_iter()
+            .map(|service| ServiceCost { month_forecast: service_forecasts.get(&service.name).copied(), ..service })
+            .collect();
+        let mut comparisons = Vec::new();
+        let window = self.config.window(today);
+        let previous_label = match (window.days(), window.whole_months()) {
+            (_, Some(1)) => "前月",
+            (1, _) => "前日",
+            (7, _) => "前週",
+            _ => "前期間",
+        };
+        let previous = if self.config.compare_previous || self.config.rank_changes || self.config.movers_count.is_some() {
+            Some(cost_explorer.fetch_previous_costs(today, &self.service_aliases).await?)
+        } else {
+            None
+        };
+        let movers = self.config.movers_count.zip(previous.as_ref())
+            .map(|(count, previous)| Breakdown {
+                title: match self.config.movers_order {
+                    MoversOrder::Amount => format!("{previous_label}からの増加額が大きいサービス"),
+                    MoversOrder::Percent => format!("{previous_label}からの増加率が大きいサービス"),
+                },
+                items: top_movers(&services, previous, count, self.config.movers_order, self.config.movers_floor),
+            })
+            .filter(|breakdown| !breakdown.items.is_empty());
+        let rank_changes = previous.as_ref()
+            .filter(|_| self.config.rank_changes)
+            .map(|previous| rank_changes(&services, previous));
+        let new_services = if self.config.detect_new_services {
+            let preceding = cost_explorer.fetch_preceding_costs(today, NEW_SERVICE_LOOKBACK_DAYS, &self.service_aliases).await?;
+            Some(Breakdown {
+                title: format!("🆕 初めての請求 (直近{NEW_SERVICE_LOOKBACK_DAYS}日に請求がなかったサービス)"),
+                items: new_services(&services, &preceding),
+            })
+            .filter(|breakdown| !breakdown.items.is_empty())
+        } else {
+            None
+        };
+        let disappeared_services = if self.config.detect_disappeared_services {
+            let last_week = cost_explorer.fetch_preceding_costs(today, DISAPPEARED_SERVICE_LOOKBACK_DAYS, &self.service_aliases).await?;
+            Some(Breakdown {
+                title: format!("{}に請求がなくなったサービス (直近{DISAPPEARED_SERVICE_LOOKBACK_DAYS}日の料金)", self.config.period_label()),
+                items: disappeared_services(&services, &last_week),
+            })
+            .filter(|breakdown| !breakdown.items.is_empty())
+        } else {
+            None
+        };
+        let services = match previous.filter(|_| self.config.compare_previous) {
+            Some(previous) => {
+                comparisons.push(Comparison { label: format!("{previous_label}比"), baseline: previous.values().sum() });
+                with_previous(services, &previous)
+            }
+            None => services,
+        };
+        if self.config.compare_last_week {
+            let last_week = cost_explorer.fetch_shifted_costs(today, 7, &self.service_aliases).await?;
+            let label = if self.config.window(today).days() == 1 { "先週同曜日比" } else { "前週比" };
+            comparisons.push(Comparison { label: label.to_string(), baseline: last_week.values().sum() });
+        }
+        if self.config.compare_last_month {
+            let window = self.config.window(today);
+            let last_month = cost_explorer.fetch_shifted_costs(today, window.days_since_last_month(), &self.service_aliases).await?;
+            let label = if window.days() == 1 { "先月同日比" } else { "前月同期比" };
+            comparisons.push(Comparison { label: label.to_string(), baseline: last_month.values().sum() });
+        }
+        let services = if self.service_budgets.is_empty() {
+            services
+        } else {
+            let month_to_date = cost_explorer.fetch_month_to_date_by_service(today, &self.service_aliases).await?;
+            mark_over_budget(services, &month_to_date, &self.service_budgets)
+        };
+        let services = match rank_changes {
+            Some(changes) => services.into_iter()
+                .zip(changes)
+                .map(|(mut service, change)| {
+                    service.annotations.rank_change = Some(change);
+                    service
+                })
+                .collect(),
+            None => services,
+        };
+        let top_service = cost_and_usage.groups.first().and_then(|group| group.keys.as_ref()?.first());
+        let services = match top_service.filter(|_| self.config.top_service_usage_types) {
+            Some(service) => {
+                let usage_types = cost_explorer.fetch_top_usage_types(today, service, TOP_USAGE_TYPE_COUNT).await?;
+                with_top_usage_types(services, &usage_types)
+            }
+            None => services,
+        };
+        // 並べて表示する指標も返ってくるので、ランキングの料金から合計する
+        let period_total: f64 = services.iter().map(|service| service.amount).sum();
+        println!("total_cost: {}", period_total);
+
+        let monthly_cost = cost_explorer.fetch_current_month_cost(today).await?;
+        let last_month_cost = cost_explorer.fetch_last_month_cost(today).await?;
+        let mut breakdowns: Vec<_> = [
+            cost_explorer.fetch_daily_chart(today).await?,
+            cost_explorer.fetch_month_to_date_ranking(today, &self.service_aliases).await?,
+            new_services,
+            disappeared_services,
+            movers,
+            cost_explorer.fetch_region_breakdown(today).await?,
+            cost_explorer.fetch_focus_service_breakdown(today).await?,
+            cost_explorer.fetch_purchase_option_breakdown(today).await?,
+            cost_explorer.fetch_data_transfer_breakdown(today).await?,
+            cost_explorer.fetch_cloudwatch_breakdown(today).await?,
+            cost_explorer.fetch_separated_charges(today).await?,
+            cost_explorer.fetch_marketplace_charges(today).await?,
+            cost_explorer.fetch_hourly_breakdown(today, self.timezone).await?,
+            cost_explorer.fetch_resource_breakdown(today).await?,
+            cost_explorer.fetch_lambda_function_breakdown(today).await?,
+            cost_explorer.fetch_anomalies(today).await?,
+            cost_explorer.fetch_savings_plans_utilization(today).await?,
+            cost_explorer.fetch_savings_plans_coverage(today).await?,
+            cost_explorer.fetch_reservations(today).await?,
+            cost_explorer.fetch_expiring_commitments(today).await?,
+            cost_explorer.fetch_purchase_recommendations().await?,
+            cost_explorer.fetch_rightsizing_recommendations().await?,
+        ].into_iter().flatten().collect();
+        breakdowns.extend(cost_explorer.fetch_drill_down(today, &cost_and_usage.groups, &self.service_aliases).await?);
+        if let Some(cur) = &self.cur {
+            let window = self.config.window(today);
+            breakdowns.extend(cur::fetch_breakdowns(cur, window.start, window.end).await?);
+        }
+        let last_year_month_to_date = if self.sections.year_over_year {
+            Some(cost_explorer.fetch_last_year_month_to_date(today).await?)
+        } else {
+            None
+        };
+        let quarter_forecast = if self.sections.quarter_forecast {
+            Some(cost_explorer.fetch_quarter_forecast(today).await?)
+        } else {
+            None
+        };
+        let year_forecast = if self.sections.year_forecast {
+            Some(cost_explorer.fetch_year_forecast(today).await?)
+        } else {
+            None
+        };
+        let moving_average = if self.sections.moving_average {
+            Some(cost_explorer.fetch_moving_average(today, MOVING_AVERAGE_DAYS).await?)
+        } else {
+            None
+        };
+        // 1 日より長い集計期間は日ごとの料金と比べられないので判定しない
+        let daily_history = if self.spike_detection && self.config.window(today).days() == 1 {
+            cost_explorer.fetch_daily_history(today, SPIKE_HISTORY_DAYS).await?
+        } else {
+            Vec::new()
+        };
+        let recent_average = if self.quiet {
+            Some(cost_explorer.fetch_recent_average(today, QuietConfig::RECENT_DAYS).await?)
+        } else {
+            None
+        };
+        let month_composition = if self.sections.month_composition {
+            cost_explorer.fetch_month_to_date_composition(today).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Costs {
+            period: self.config.period_label(),
+            period_total,
+            estimated: cost_and_usage.estimated,
+            moving_average,
+            daily_history,
+            comparisons,
+            untagged_cost: cost_explorer.fetch_untagged_cost(today).await?,
+            environments: cost_explorer.fetch_environment_costs(today).await?,
+            recent_average,
+            monthly_cost,
+            last_year_month_to_date,
+            ai_spotlight: cost_explorer.fetch_ai_spotlight(today).await?,
+            forecast: forecast.map(|forecast| forecast.mean),
+            forecast_interval: forecast.and_then(|forecast| forecast.interval),
+            quarter_forecast,
+            year_forecast,
+            last_month_cost: Some(last_month_cost),
+            services,
+            breakdowns,
+            month_composition,
+            notice: None,
+        })
+    }
+
+    fn is_unavailable(&self, e: &MyError) -> bool {
+        cost_explorer::is_unavailable(e)
+    }
+}
+
+/// Athena で問い合わせる CUR。予測はない
+pub struct CurAthenaProvider {
+    cur: CurConfig,
+    config: CostExplorerConfig,
+    service_aliases: HashMap<String, String>,
+}
+
+impl CurAthenaProvider {
+    pub fn new(config: &Config) -> Result<Self, MyError> {
+        Ok(Self {
+            cur: config.cur.clone().ok_or("設定エラー: provider を cur にする場合は cur (CUR_DATABASE) も必要です")?,
+            config: config.cost_explorer.clone(),
+            service_aliases: config.service_aliases.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl CostProvider for CurAthenaProvider {
+    fn name(&self) -> &'static str {
+        "CUR"
+    }
+
+    async fn fetch(&self, today: NaiveDate) -> Result<Costs, MyError> {
+        let window = self.config.window(today);
+        let services: Vec<_> = cur::fetch_service_costs(&self.cur, window.start, window.end).await?
+            .into_iter()
+            .map(|service| ServiceCost {
+                name: self.service_aliases.get(&service.name).cloned().unwrap_or(service.name),
+                ..service
+            })
+            .collect();
+        let (current_month, last_month) = (self.config.current_month(today)?, self.config.last_month(today)?);
+        Ok(Costs {
+            period: self.config.period_label(),
+            period_total: services.iter().map(|service| service.amount).sum(),
+            estimated: false,
+            moving_average: None,
+            daily_history: Vec::new(),
+            comparisons: Vec::new(),
+            untagged_cost: None,
+            environments: Vec::new(),
+            recent_average: None,
+            monthly_cost: cur::fetch_total(&self.cur, current_month.start, current_month.end).await?,
+            last_year_month_to_date: None,
+            ai_spotlight: None,
+            forecast: None,
+            forecast_interval: None,
+            quarter_forecast: None,
+            year_forecast: None,
+            last_month_cost: Some(cur::fetch_total(&self.cur, last_month.start, last_month.end).await?),
+            services,
+            breakdowns: cur::fetch_breakdowns(&self.cur, window.start, window.end).await?,
+            month_composition: Vec::new(),
+            notice: None,
+        })
+    }
+}
+
+/// CloudWatch の請求見込み額。合計だけで、予測・先月・ランキングはない
+pub struct CloudWatchProvider {
+    config: CostExplorerConfig,
+}
+
+impl CloudWatchProvider {
+    pub fn new(config: &Config) -> Self {
+        Self { config: config.cost_explorer.clone() }
+    }
+}
+
+#[async_trait]
+impl CostProvider for CloudWatchProvider {
+    fn name(&self) -> &'static str {
+        "CloudWatch"
+    }
+
+    async fn fetch(&self, today: NaiveDate) -> Result<Costs, MyError> {
+        let window = self.config.window(today);
+        let charges = cloudwatch::fetch_estimated_charges(today, window.start, window.end).await?;
+        Ok(Costs {
+            period: self.config.period_label(),
+            period_total: charges.period_total,
+            estimated: true,
+            monthly_cost: charges.monthly_cost,
+            notice: Some("CloudWatch の請求見込み額から合計だけを載せています".to_string()),
+            ..Costs::default()
+        })
+    }
+}
+
+/// 集計期間に請求があり、直前の期間には請求がなかったサービス
+fn new_services(services: &[ServiceCost], preceding: &HashMap<String, f64>) -> Vec<ServiceCost> {
+    services.iter()
+        // 表示の最小単位 (0.01 USD) 未満の料金は請求がなかったものとする
+        .filter(|service| service.amount >= 0.005 && preceding.get(&service.name).is_none_or(|cost| *cost < 0.005))
+        .cloned()
+        .collect()
+}
+
+/// 直前の期間に `DISAPPEARED_SERVICE_FLOOR` 以上の請求があり、集計期間には請求がなかったサービス。
+/// 料金は 0 とし、直前の期間の料金を比較の基準として添える
+fn disappeared_services(services: &[ServiceCost], preceding: &HashMap<String, f64>) -> Vec<ServiceCost> {
+    let mut disappeared: Vec<_> = preceding.iter()
+        .filter(|(_, cost)| **cost >= DISAPPEARED_SERVICE_FLOOR)
+        .filter(|(name, _)| !services.iter().any(|service| service.name == **name && service.amount >= 0.005))
+        .map(|(name, cost)| ServiceCost { previous: Some(*cost), ..ServiceCost::new(name.clone(), 0.0) })
+        .collect();
+    disappeared.sort_by(|a, b| b.previous.unwrap_or(0.0).total_cmp(&a.previous.unwrap_or(0.0)));
+    disappeared
+}
+
+/// 直前の期間の順位からの変化 (例: `↑2`、`↓1`、`NEW`)。順位が変わらなければ `→`
+fn rank_changes(services: &[ServiceCost], previous: &HashMap<String, f64>) -> Vec<String> {
+    let mut previous_ranking: Vec<_> = previous.iter().filter(|(_, cost)| **cost >= 0.005).collect();
+    previous_ranking.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    services.iter()
+        .enumerate()
+        .map(|(rank, service)| match previous_ranking.iter().position(|(name, _)| **name == service.name) {
+            None => "NEW".to_string(),
+            Some(previous_rank) if previous_rank > rank => format!("↑{}", previous_rank - rank),
+            Some(previous_rank) if previous_rank < rank => format!("↓{}", rank - previous_rank),
+            Some(_) => "→".to_string(),
+        })
+        .collect()
+}
+
+/// 各サービスに直前の期間の料金を添える。直前の期間になかったサービスは 0 とする
+fn with_previous(services: Vec<ServiceCost>, previous: &HashMap<String, f64>) -> Vec<ServiceCost> {
+    services.into_iter()
+        .map(|service| ServiceCost { previous: Some(previous.get(&service.name).copied().unwrap_or(0.0)), ..service })
+        .collect()
+}
+
+/// 直前の期間から料金が増えたサービスを、`order` の順に `count` 件返す。
+/// 増加率の順では、集計期間の料金が `floor` 未満のサービスを除く
+fn top_movers(services: &[ServiceCost], previous: &HashMap<String, f64>, count: usize, order: MoversOrder, floor: f64) -> Vec<ServiceCost> {
+    let mut movers: Vec<_> = with_previous(services.to_vec(), previous).into_iter()
+        .filter(|service| service.amount > service.previous.unwrap_or(0.0))
+        .filter(|service| order == MoversOrder::Amount || service.amount >= floor)
+        .collect();
+    let growth = |service: &ServiceCost| {
+        let previous = service.previous.unwrap_or(0.0);
+        match order {
+            MoversOrder::Amount => service.amount - previous,
+            MoversOrder::Percent if previous > 0.0 => service.amount / previous - 1.0,
+            MoversOrder::Percent => f64::INFINITY,
+        }
+    };
+    movers.sort_by(|a, b| growth(b).total_cmp(&growth(a)));
+    movers.truncate(count);
+    movers
+}
+
+/// ランキング 1 位のサービスに、利用タイプごとの料金の割合を添える (例: `EC2 (内訳: BoxUsage:m5.large 62% / …)`)
+fn with_top_usage_types(mut services: Vec<ServiceCost>, usage_types: &[ServiceCost]) -> Vec<ServiceCost> {
+    let Some(top) = services.first_mut().filter(|top| top.amount > 0.0 && !usage_types.is_empty()) else {
+        return services;
+    };
+    top.annotations.usage_type_shares = usage_types.iter()
+        .map(|usage_type| (usage_type.name.clone(), usage_type.amount / top.amount * 100.0))
+        .collect();
+    services
+}
+
+/// 今月の現時点料金が予算を超えたサービスに、超過率の目印を付ける
+fn mark_over_budget(services: Vec<ServiceCost>, month_to_date: &HashMap<String, f64>, budgets: &HashMap<String, f64>) -> Vec<ServiceCost> {
+    services.into_iter()
+        .map(|mut service| {
+            service.annotations.over_budget = budgets.get(&service.name)
+                .zip(month_to_date.get(&service.name))
+                .filter(|(budget, cost)| cost > budget)
+                .map(|(budget, cost)| (cost / budget - 1.0) * 100.0);
+            service
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::fixtures;
+
+    /// 決まった料金を返す取得元
+    struct FixedProvider(Costs);
+
+    #[async_trait]
+    impl CostProvider for FixedProvider {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        async fn fetch(&self, _today: NaiveDate) -> Result<Costs, MyError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_into_report() {
+        let provider: Box<dyn CostProvider> = Box::new(FixedProvider(Costs {
+            period: "前々日".to_string(),
+            period_total: 12.5,
+            monthly_cost: 120.0,
+            ..Costs::default()
+        }));
+        let today = NaiveDate::from_ymd_opt(2026, 10, 3).unwrap();
+        let report = provider.fetch(today).await.unwrap().into_report(fixtures::exchange(), &Config::default());
+        assert_eq!(report.daily_total, 12.5);
+        assert_eq!(report.monthly_cost, 120.0);
+        assert!(!report.sections.forecast);
+
+        let with_forecast = Costs { forecast: Some(310.0), ..Costs::default() };
+        assert!(with_forecast.into_report(fixtures::exchange(), &Config::default()).sections.forecast);
+    }
+
+    #[test]
+    fn test_mark_over_budget() {
+        let service = |name: &str| ServiceCost::new(name.to_string(), 10.0);
+        let month_to_date = HashMap::from([("EC2".to_string(), 345.0), ("S3".to_string(), 20.0)]);
+        let budgets = HashMap::from([("EC2".to_string(), 300.0), ("S3".to_string(), 50.0)]);
+        let marked = mark_over_budget(vec![service("EC2"), service("S3"), service("Lambda")], &month_to_date, &budgets);
+        assert_eq!(marked[0].name, "EC2");
+        assert_eq!(marked[0].label(), "EC2 ⚠ 予算超過 +15%");
+        assert_eq!(marked[1].label(), "S3");
+        assert_eq!(marked[2].label(), "Lambda");
+    }
+
+    #[test]
+    fn test_with_top_usage_types() {
+        let service = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let usage_types = [service("APN1-BoxUsage:m5.large", 62.0), service("APN1-NatGateway-Hours", 15.0)];
+        let services = with_top_usage_types(vec![service("EC2", 100.0), service("S3", 10.0)], &usage_types);
+        assert_eq!(services[0].name, "EC2");
+        assert_eq!(services[0].label(), "EC2 (内訳: APN1-BoxUsage:m5.large 62% / APN1-NatGateway-Hours 15%)");
+        assert_eq!(services[1].label(), "S3");
+        assert_eq!(with_top_usage_types(vec![service("EC2", 100.0)], &[])[0].label(), "EC2");
+    }
+
+    #[test]
+    fn test_top_movers() {
+        let service = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let previous = HashMap::from([("EC2".to_string(), 95.0), ("S3".to_string(), 3.0), ("Lambda".to_string(), 2.0)]);
+        let services = [service("EC2", 100.0), service("S3", 10.0), service("Lambda", 1.0), service("Bedrock", 6.0), service("SQS", 0.5)];
+        let names = |movers: Vec<ServiceCost>| movers.into_iter().map(|service| service.name).collect::<Vec<_>>();
+        let movers = top_movers(&services, &previous, 2, MoversOrder::Amount, 1.0);
+        assert_eq!(movers[1].previous, Some(0.0));
+        assert_eq!(names(movers), ["S3", "Bedrock"]);
+        assert_eq!(names(top_movers(&services, &previous, 3, MoversOrder::Percent, 1.0)), ["Bedrock", "S3", "EC2"]);
+    }
+
+    #[test]
+    fn test_new_services() {
+        let service = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let preceding = HashMap::from([("EC2".to_string(), 300.0), ("Kendra".to_string(), 0.0)]);
+        let found = new_services(&[service("EC2", 10.0), service("Kendra", 12.4), service("Bedrock", 3.0), service("SQS", 0.001)], &preceding);
+        let names: Vec<_> = found.iter().map(|service| service.name.as_str()).collect();
+        assert_eq!(names, ["Kendra", "Bedrock"]);
+    }
+
+    #[test]
+    fn test_disappeared_services() {
+        let service = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let last_week = HashMap::from([("EC2".to_string(), 70.0), ("NAT Gateway".to_string(), 21.0), ("SageMaker".to_string(), 35.0), ("SQS".to_string(), 0.2)]);
+        let found = disappeared_services(&[service("EC2", 10.0), service("NAT Gateway", 0.0)], &last_week);
+        let names: Vec<_> = found.iter().map(|service| service.name.as_str()).collect();
+        assert_eq!(names, ["SageMaker", "NAT Gateway"]);
+        assert_eq!(found[0].previous, Some(35.0));
+    }
+
+    #[test]
+    fn test_rank_changes() {
+        let service = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let previous = HashMap::from([("EC2".to_string(), 50.0), ("RDS".to_string(), 80.0), ("S3".to_string(), 5.0), ("Lambda".to_string(), 10.0)]);
+        let services = [service("EC2", 90.0), service("RDS", 70.0), service("Kendra", 12.0), service("S3", 8.0), service("Lambda", 3.0)];
+        assert_eq!(rank_changes(&services, &previous), ["↑1", "↓1", "NEW", "→", "↓2"]);
+    }
+}
@@ -0,0 +1,938 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::MyError;
+use crate::cost_explorer::window::Window;
+
+/// 通知の件名
+pub const TITLE: &str = "AWS 利用料金レポート";
+
+/// 週次レポートの件名
+pub const WEEKLY_TITLE: &str = "AWS 利用料金 週次レポート";
+
+/// 月次締めレポートの件名
+pub const MONTHLY_TITLE: &str = "AWS 利用料金 月次締めレポート";
+
+/// 閾値を超えたときの件名
+pub const ALERT_TITLE: &str = "⚠️ AWS 利用料金アラート";
+
+/// 移動平均をとる日数
+pub const MOVING_AVERAGE_DAYS: u32 = 7;
+
+/// 日割りで延ばした今月の推定の項目名
+const EXTRAPOLATION_LABEL: &str = "今月の推定(日割り)";
+
+/// レポートの種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportType {
+    /// 前々日などの 1 日の料金
+    #[default]
+    Daily,
+    /// 直近 7 日の料金。日別のグラフと前週比を載せる
+    Weekly,
+    /// 確定した先月の料金。前月からの増加と予算の結果を載せる
+    Monthly,
+}
+
+impl ReportType {
+    /// 通常の件名
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Daily => TITLE,
+            Self::Weekly => WEEKLY_TITLE,
+            Self::Monthly => MONTHLY_TITLE,
+        }
+    }
+}
+
+impl FromStr for ReportType {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            "monthly" => Ok(Self::Monthly),
+            other => Err(format!("daily・weekly・monthly のいずれかを指定してください: {other}").into()),
+        }
+    }
+}
+
+/// 通知する日次レポート。金額はすべて USD
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub report_type: ReportType,
+    pub exchange: Exchange,
+    /// ランキングの集計期間の表示名 (例: `前々日`、`2026-10-01〜2026-10-07`)
+    pub period: String,
+    /// 集計期間 (既定は前々日) の合計料金
+    pub daily_total: f64,
+    /// 集計期間の料金に速報値を含む。後から変わることがある
+    pub estimated: bool,
+    /// 集計期間の最終日までの 1 日あたりの移動平均。セクションが無効なら `None`
+    pub moving_average: Option<f64>,
+    /// 集計期間の直前の日ごとの合計料金 (古い順)。スパイクの判定が無効なら空
+    pub daily_history: Vec<f64>,
+    /// 集計期間の合計料金と比べる基準 (前日など)
+    pub comparisons: Vec<Comparison>,
+    /// 集計期間の料金のうち、設定したコスト配分タグのない分。設定されていなければ `None`
+    pub untagged_cost: Option<f64>,
+    /// 集計期間の環境 (prod・stg・dev など) 別の料金 (降順)。設定されていなければ空
+    pub environments: Vec<ServiceCost>,
+    /// 集計期間の直前 7 日の 1 日あたりの平均料金。通知を省く設定がなければ `None`
+    pub recent_average: Option<f64>,
+    /// 今月の現時点料金
+    pub monthly_cost: f64,
+    /// 前年の同じ月の同じ日までの料金。セクションが無効なら `None`
+    pub last_year_month_to_date: Option<f64>,
+    /// AI サービスと GPU インスタンスの今月の現時点料金。セクションが無効なら `None`
+    pub ai_spotlight: Option<Spotlight>,
+    /// 今月の予測
+    pub forecast: f64,
+    /// 今月の予測の 80% 予測区間。取得元が返さなければ `None`
+    pub forecast_interval: Option<ForecastInterval>,
+    /// 今月の現時点料金を日割りで月末まで延ばした推定。予測のセクションが無効なら `None`
+    pub extrapolation: Option<f64>,
+    /// 今四半期の予測。セクションが無効なら `None`
+    pub quarter_forecast: Option<f64>,
+    /// 今年の予測。セクションが無効なら `None`
+    pub year_forecast: Option<f64>,
+    /// 先月の合計料金。取得元が返さなければ `None`
+    pub last_month_cost: Option<f64>,
+    /// 月の予算に対する消化ペース。予算が未設定なら `None`
+    pub budget_runway: Option<BudgetRunway>,
+    /// 月次締めレポートで集計期間の合計と比べる月の予算 (USD)。予算が未設定なら `None`
+    pub budget_outcome: Option<f64>,
+    /// 集計期間のサービス別料金 (降順)
+    pub services: Vec<ServiceCost>,
+    pub display_count: usize,
+    /// ランキングの上位 3 件の順位をメダルで表す
+    pub ranking_medals: bool,
+    /// この額 (USD) 未満のサービスはランキングに出さず、`その他` にまとめる
+    pub ranking_floor: f64,
+    /// 閾値超過などのアラート理由。空なら通常の日次レポート
+    pub alerts: Vec<String>,
+    pub sections: Sections,
+    /// 件名の前に付ける接頭辞 (例: `[prod]`)
+    pub message_prefix: Option<String>,
+    /// ランキングの後に載せる内訳 (リージョン別など)
+    pub breakdowns: Vec<Breakdown>,
+    /// 今月の現時点料金のレコードタイプ別の内訳 (利用・税金・クレジットなど)。セクションが無効なら空
+    pub month_composition: Vec<ServiceCost>,
+    /// 本文の先頭に載せる注意書き (縮小版のレポートであることなど)
+    pub notice: Option<String>,
+}
+
+/// 集計期間の合計料金と比べる基準
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    /// 項目名 (例: `前日比`)
+    pub label: String,
+    /// 基準の料金 (USD)
+    pub baseline: f64,
+}
+
+/// 一部のサービスの今月の現時点料金と、先月の同じ日数の料金 (USD)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Spotlight {
+    pub amount: f64,
+    pub previous: f64,
+}
+
+/// 予測区間 (USD)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ForecastInterval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// 月の予算に対する消化ペース (USD)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BudgetRunway {
+    pub budget: f64,
+    /// 今月の 1 日あたりの平均料金
+    pub daily_burn: f64,
+    /// 予算の残り。超えていれば負
+    pub remaining: f64,
+    /// 今のペースで今月中に予算を使い切る日。すでに超えているか、月末まで持つなら `None`
+    pub exhausted_on: Option<NaiveDate>,
+}
+
+impl BudgetRunway {
+    /// `current_month` は `today` を含む今月
+    pub fn new(budget: f64, monthly_cost: f64, today: NaiveDate, current_month: Window) -> Self {
+        let daily_burn = monthly_cost / elapsed_days(today, current_month) as f64;
+        let remaining = budget - monthly_cost;
+        let exhausted_on = (remaining > 0.0 && daily_burn > 0.0)
+            .then(|| today + chrono::Duration::days((remaining / daily_burn).ceil() as i64))
+            .filter(|date| *date < current_month.end);
+        Self { budget, daily_burn, remaining, exhausted_on }
+    }
+
+    fn format_exhaustion(&self) -> String {
+        match self.exhausted_on {
+            _ if self.remaining <= 0.0 => "超過済み".to_string(),
+            Some(date) => date.to_string(),
+            None => "今月は予算内の見込み".to_string(),
+        }
+    }
+}
+
+/// 今月の経過日数。今日の料金はまだ集計中なので昨日までの日数にする (月の初日なら 1)
+fn elapsed_days(today: NaiveDate, current_month: Window) -> i64 {
+    (today - current_month.start).num_days().max(1)
+}
+
+/// 今月の現時点料金を、経過日数あたりの平均で月末まで延ばす
+pub fn extrapolate(monthly_cost: f64, today: NaiveDate, current_month: Window) -> f64 {
+    monthly_cost / elapsed_days(today, current_month) as f64 * current_month.days() as f64
+}
+
+/// ランキングとは別の切り口の料金の内訳
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Breakdown {
+    /// 見出し (例: `前々日のリージョン別料金`)
+    pub title: String,
+    /// 降順の料金。表示件数を超えた分はまとめた 1 行にしてある
+    pub items: Vec<ServiceCost>,
+}
+
+/// レポートに含めるセクション
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sections {
+    /// 今月の予測
+    pub forecast: bool,
+    /// 今月の現時点料金のレコードタイプ別の内訳
+    pub month_composition: bool,
+    /// 今四半期の予測
+    pub quarter_forecast: bool,
+    /// 今年の予測
+    pub year_forecast: bool,
+    /// 今月の現時点料金の前年同期比
+    pub year_over_year: bool,
+    /// 集計期間の合計料金に添える移動平均
+    pub moving_average: bool,
+}
+
+impl Default for Sections {
+    fn default() -> Self {
+        Self { forecast: true, month_composition: false, quarter_forecast: false, year_forecast: false, year_over_year: false, moving_average: false }
+    }
+}
+
+/// レポートの重要度。通知先の振り分けに使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Normal,
+    Alert,
+}
+
+/// 表示通貨と USD からの換算レート
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Exchange {
+    /// ISO 4217 の通貨コード
+    pub currency: String,
+    /// 1 USD あたりの表示通貨の額
+    pub rate: f64,
+}
+
+impl Exchange {
+    pub fn usd() -> Self {
+        Self { currency: "USD".to_string(), rate: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceCost {
+    pub name: String,
+    pub amount: f64,
+    /// 料金と並べて表示する別の指標の値
+    pub secondary: Option<Secondary>,
+    /// 今月末の料金の予測 (USD)。ランキング上位のサービスだけ
+    pub month_forecast: Option<f64>,
+    /// 比べる期間の料金 (USD)。比べない設定なら `None`
+    pub previous: Option<f64>,
+    /// 名前の後ろに添えて表示する目印
+    pub annotations: Annotations,
+}
+
+/// サービス名に添える目印。名前は閾値や内訳との突き合わせに使うので、目印は表示するときに付ける
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Annotations {
+    /// 今月の現時点料金が予算を超えた割合 (%)
+    pub over_budget: Option<f64>,
+    /// 直前の期間の順位からの変化 (例: `↑2`、`NEW`)
+    pub rank_change: Option<String>,
+    /// 利用タイプごとの料金がサービスの料金に占める割合 (%)。ランキング 1 位のサービスだけ
+    pub usage_type_shares: Vec<(String, f64)>,
+}
+
+impl ServiceCost {
+    /// 指標や目印を持たない料金
+    pub fn new(name: String, amount: f64) -> Self {
+        Self { name, amount, secondary: None, month_forecast: None, previous: None, annotations: Annotations::default() }
+    }
+
+    /// 目印を添えた表示名 (例: `EC2 ⚠ 予算超過 +15%`)
+    pub fn label(&self) -> String {
+        let mut label = self.name.clone();
+        if let Some(over) = self.annotations.over_budget {
+            label.push_str(&format!(" ⚠ 予算超過 +{over:.0}%"));
+        }
+        if let Some(change) = &self.annotations.rank_change {
+            label.push_str(&format!(" {change}"));
+        }
+        if !self.annotations.usage_type_shares.is_empty() {
+            let shares: Vec<_> = self.annotations.usage_type_shares.iter()
+                .map(|(usage_type, share)| format!("{usage_type} {share:.0}%"))
+                .collect();
+            label.push_str(&format!(" (内訳: {})", shares.join(" / ")));
+        }
+        label
+    }
+}
+
+/// ランキングで料金と並べて表示する値
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Secondary {
+    /// 別の指標の料金 (USD)
+    Cost { label: &'static str, amount: f64 },
+    /// 使用量。単位はサービスや利用タイプによって異なる
+    Usage { quantity: f64 },
+}
+
+impl Secondary {
+    fn format(&self, exchange: &Exchange) -> String {
+        match self {
+            Self::Cost { label, amount } => format!("{label}: {}", format_cost(*amount, exchange)),
+            Self::Usage { quantity } => format!("使用量: {quantity:.2}"),
+        }
+    }
+}
+
+impl Report {
+    pub fn format_cost(&self, cost_usd: f64) -> String {
+        format_cost(cost_usd, &self.exchange)
+    }
+
+    pub fn severity(&self) -> Severity {
+        if self.alerts.is_empty() { Severity::Normal } else { Severity::Alert }
+    }
+
+    /// ランキングに表示するサービス
+    pub fn ranking(&self) -> impl Iterator<Item = &ServiceCost> {
+        self.services.iter().take(self.ranked_count())
+    }
+
+    /// ランキングに表示しきれなかったサービスをまとめた行
+    pub fn others(&self) -> Option<ServiceCost> {
+        roll_up_others(&self.services, self.ranked_count())
+    }
+
+    /// ランキングに表示するサービス数。料金の降順なので、下限未満のサービスは末尾にまとまっている
+    fn ranked_count(&self) -> usize {
+        self.services.iter().take(self.display_count).take_while(|service| service.amount >= self.ranking_floor).count()
+    }
+
+    /// 0 始まりの `index` 番目の順位の表示 (例: `1.`、`🥇`)
+    pub fn rank_label(&self, index: usize) -> String {
+        match (self.ranking_medals, index) {
+            (true, 0) => "🥇".to_string(),
+            (true, 1) => "🥈".to_string(),
+            (true, 2) => "🥉".to_string(),
+            _ => format!("{}.", index + 1),
+        }
+    }
+
+    /// 順位を付けたサービス名 (例: `1. AWS Lambda`)
+    pub fn ranked_name(&self, index: usize, name: &str) -> String {
+        format!("{} {name}", self.rank_label(index))
+    }
+
+    /// 順位を付けたランキングに、表示しきれなかったサービスをまとめた行を続けたもの
+    pub fn numbered_ranking(&self) -> Vec<ServiceCost> {
+        self.ranking()
+            .enumerate()
+            .map(|(i, service)| ServiceCost { name: self.ranked_name(i, &service.name), ..service.clone() })
+            .chain(self.others())
+            .collect()
+    }
+
+    /// 接頭辞を付けた件名。閾値を超えていれば警告の件名にする
+    pub fn title(&self) -> String {
+        match self.severity() {
+            Severity::Normal => self.prefixed(self.report_type.title()),
+            Severity::Alert => self.prefixed(ALERT_TITLE),
+        }
+    }
+
+    /// 接頭辞が設定されていれば先頭に付ける
+    pub fn prefixed(&self, text: &str) -> String {
+        match &self.message_prefix {
+            Some(prefix) => format!("{prefix} {text}"),
+            None => text.to_string(),
+        }
+    }
+
+    /// 合計料金の項目名と整形した金額。予測はセクションが有効なときだけ含める
+    pub fn totals(&self) -> Vec<(String, String)> {
+        let mut totals = vec![(self.daily_label(), self.format_daily_total())];
+        totals.extend(self.comparison_lines());
+        totals.extend(self.untagged_line());
+        totals.extend(self.environment_line());
+        totals.push(("現時点料金".to_string(), self.format_cost(self.monthly_cost)));
+        totals.extend(self.year_over_year_line());
+        totals.extend(self.ai_spotlight_line());
+        if self.sections.forecast {
+            totals.push(("今月の予測".to_string(), self.format_forecast()));
+        }
+        if let Some(extrapolation) = self.extrapolation {
+            totals.push((EXTRAPOLATION_LABEL.to_string(), self.format_cost(extrapolation)));
+        }
+        totals.extend(self.long_range_forecasts().into_iter().map(|(label, cost)| (label.to_string(), self.format_cost(cost))));
+        totals.extend(self.budget_lines());
+        totals.extend(self.last_month_line());
+        totals
+    }
+
+    /// 予算の残り・1 日あたりの平均・予算を使い切る見込み。予算が未設定なら空
+    fn budget_lines(&self) -> Vec<(String, String)> {
+        if let Some(budget) = self.budget_outcome {
+            let outcome = format!("{} (予算 {})", format_change(self.daily_total, budget, &self.exchange), self.format_cost(budget));
+            return vec![("予算との差".to_string(), outcome)];
+        }
+        let Some(runway) = &self.budget_runway else {
+            return Vec::new();
+        };
+        vec![
+            ("予算の残り".to_string(), format!("{} / {}", self.format_cost(runway.remaining), self.format_cost(runway.budget))),
+            ("1日あたりの平均".to_string(), self.format_cost(runway.daily_burn)),
+            ("予算到達見込み".to_string(), runway.format_exhaustion()),
+        ]
+    }
+
+    /// 今月の予測と見比べる先月の合計。取得元が返さなければ `None`。
+    /// 月次締めレポートでは集計期間の合計と同じなので載せない
+    fn last_month_line(&self) -> Option<(String, String)> {
+        if self.report_type == ReportType::Monthly {
+            return None;
+        }
+        Some(("先月の合計".to_string(), self.format_cost(self.last_month_cost?)))
+    }
+
+    /// 集計期間の合計料金の基準からの増減
+    fn comparison_lines(&self) -> Vec<(String, String)> {
+        self.comparisons.iter()
+            .map(|comparison| (comparison.label.clone(), format_change(self.daily_total, comparison.baseline, &self.exchange)))
+            .collect()
+    }
+
+    /// 集計期間の料金のうち、コスト配分タグのない分と割合 (例: `3200円($21.33) / 18%`)。設定されていなければ `None`
+    fn untagged_line(&self) -> Option<(String, String)> {
+        let untagged = self.untagged_cost?;
+        let cost = self.format_cost(untagged);
+        let line = if self.daily_total > 0.0 { format!("{cost} / {:.0}%", untagged / self.daily_total * 100.0) } else { cost };
+        Some(("未タグ付け".to_string(), line))
+    }
+
+    /// 集計期間の環境別の料金と割合を 1 行にまとめる (例: `prod 1500円($10) 80% / dev 375円($2.5) 20%`)。設定されていなければ `None`
+    fn environment_line(&self) -> Option<(String, String)> {
+        if self.environments.is_empty() {
+            return None;
+        }
+        let total: f64 = self.environments.iter().map(|environment| environment.amount).sum();
+        let line = self.environments.iter()
+            .map(|environment| {
+                let cost = format!("{} {}", environment.name, self.format_cost(environment.amount));
+                if total > 0.0 { format!("{cost} {:.0}%", environment.amount / total * 100.0) } else { cost }
+            })
+            .collect::<Vec<_>>()
+            .join(" / ");
+        Some(("環境別".to_string(), line))
+    }
+
+    /// 今月の現時点料金の前年同期からの増減。セクションが無効なら `None`
+    fn year_over_year_line(&self) -> Option<(String, String)> {
+        let last_year = self.last_year_month_to_date?;
+        Some(("前年同期比".to_string(), format_change(self.monthly_cost, last_year, &self.exchange)))
+    }
+
+    /// AI サービスと GPU インスタンスの今月の現時点料金と、先月の同じ日数からの増減。セクションが無効なら `None`
+    fn ai_spotlight_line(&self) -> Option<(String, String)> {
+        let spotlight = self.ai_spotlight?;
+        let change = format_change(spotlight.amount, spotlight.previous, &self.exchange);
+        Some(("AI サービス".to_string(), format!("{} (前月同期比 {change})", self.format_cost(spotlight.amount))))
+    }
+
+    /// 今四半期と今年の予測のうち、取得したもの
+    fn long_range_forecasts(&self) -> Vec<(&'static str, f64)> {
+        [("今四半期の予測", self.quarter_forecast), ("今年の予測", self.year_forecast)].into_iter()
+            .filter_map(|(label, forecast)| Some((label, forecast?)))
+            .collect()
+    }
+
+    /// 今月の予測。予測区間があれば幅で表す
+    pub fn format_forecast(&self) -> String {
+        match self.forecast_interval {
+            Some(interval) => format_cost_range(interval.lower, interval.upper, &self.exchange),
+            None => self.format_cost(self.forecast),
+        }
+    }
+
+    /// 集計期間の合計料金。移動平均があれば添える
+    pub fn format_daily_total(&self) -> String {
+        match self.moving_average {
+            Some(average) => format!("{} ({MOVING_AVERAGE_DAYS}日移動平均 {})", self.format_cost(self.daily_total), self.format_cost(average)),
+            None => self.format_cost(self.daily_total),
+        }
+    }
+
+    /// 集計期間の合計料金の項目名 (例: `前々日料金`)。速報値なら `(※速報値)` を添える
+    pub fn daily_label(&self) -> String {
+        if self.estimated {
+            format!("{}料金(※速報値)", self.period)
+        } else {
+            format!("{}料金", self.period)
+        }
+    }
+
+    /// ランキングの見出し (例: `■前々日の料金ランキング`)
+    pub fn ranking_title(&self) -> String {
+        format!("■{}の料金ランキング", self.period)
+    }
+
+    /// プッシュ通知向けの短い本文
+    pub fn headline(&self) -> String {
+        self.totals().iter()
+            .map(|(label, cost)| format!("{label}:{cost}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// プレーンテキストのレポート本文
+    pub fn to_text(&self) -> Result<String, MyError> {
+        Ok(format!("{}{}{}", self.summary_text()?, self.ranking_text()?, self.breakdowns_text()?))
+    }
+
+    /// ランキングを除いた概要 (アラートと合計)
+    pub fn summary_text(&self) -> Result<String, MyError> {
+        let mut text = String::new();
+        if self.message_prefix.is_some() {
+            writeln!(text, "{}", self.title())?;
+        }
+        if let Some(notice) = &self.notice {
+            writeln!(text, "※{notice}")?;
+        }
+        if !self.alerts.is_empty() {
+            writeln!(text, "■⚠️ アラート: 閾値を超えています")?;
+            for alert in &self.alerts {
+                writeln!(text, "・{alert}")?;
+            }
+            writeln!(text, "--------------")?;
+        }
+        writeln!(text, "{}:{}", self.daily_label(), self.format_daily_total())?;
+        for (label, change) in self.comparison_lines() {
+            writeln!(text, "　{label}: {change}")?;
+        }
+        if let Some((label, cost)) = self.untagged_line() {
+            writeln!(text, "　{label}: {cost}")?;
+        }
+        if let Some((label, costs)) = self.environment_line() {
+            writeln!(text, "　{label}: {costs}")?;
+        }
+        writeln!(text, "--------------")?;
+        writeln!(text, "現時点料金:{}", self.format_cost(self.monthly_cost))?;
+        if let Some((label, change)) = self.year_over_year_line() {
+            writeln!(text, "　{label}: {change}")?;
+        }
+        if let Some(composition) = self.composition_text() {
+            writeln!(text, "　内訳: {composition}")?;
+        }
+        if let Some((label, cost)) = self.ai_spotlight_line() {
+            writeln!(text, "　{label}: {cost}")?;
+        }
+        if self.sections.forecast {
+            writeln!(text, "今月の予測:{}", self.format_forecast())?;
+        }
+        if let Some(extrapolation) = self.extrapolation {
+            writeln!(text, "{EXTRAPOLATION_LABEL}:{}", self.format_cost(extrapolation))?;
+        }
+        for (label, cost) in self.long_range_forecasts() {
+            writeln!(text, "{label}:{}", self.format_cost(cost))?;
+        }
+        for (label, value) in self.budget_lines().into_iter().chain(self.last_month_line()) {
+            writeln!(text, "{label}:{value}")?;
+        }
+        Ok(text)
+    }
+
+    /// 現時点料金のレコードタイプ別の内訳を 1 行にまとめる。内訳がなければ `None`
+    pub fn composition_text(&self) -> Option<String> {
+        if self.month_composition.is_empty() {
+            return None;
+        }
+        Some(self.month_composition.iter()
+            .map(|item| format!("{} {}", item.name, self.format_cost(item.amount)))
+            .collect::<Vec<_>>()
+            .join(" / "))
+    }
+
+    /// ランキングを省略しない全サービスの内訳
+    pub fn full_breakdown_text(&self) -> Result<String, MyError> {
+        let formatted_cost_per_service = format_service_costs(&self.services, &self.exchange, usize::MAX, false)?;
+        Ok(format!("■{}の全サービスの料金\n{formatted_cost_per_service}\n", self.period))
+    }
+
+    /// サービス別ランキング
+    pub fn ranking_text(&self) -> Result<String, MyError> {
+        let formatted_cost_per_service = format_service_costs(&self.numbered_ranking(), &self.exchange, usize::MAX, true)?;
+        Ok(format!("{}\n{formatted_cost_per_service}\n", self.ranking_title()))
+    }
+
+    /// ランキングの後に載せる内訳
+    pub fn breakdowns_text(&self) -> Result<String, MyError> {
+        let mut text = String::new();
+        for breakdown in &self.breakdowns {
+            let formatted = format_service_costs(&breakdown.items, &self.exchange, usize::MAX, false)?;
+            writeln!(text, "■{}\n{formatted}", breakdown.title)?;
+        }
+        Ok(text)
+    }
+}
+
+/// 表示通貨の額と USD の額を並べて整形する。表示通貨が USD なら USD の額だけを返す
+pub fn format_cost(cost_usd: f64, exchange: &Exchange) -> String {
+    let rounded_usd = Decimal::from_f64(cost_usd).map(|d| d.round_dp(2)).unwrap_or_else(|| Decimal::ZERO);
+    let converted = cost_usd * exchange.rate;
+    match exchange.currency.as_str() {
+        "USD" => format!("${rounded_usd}"),
+        "JPY" => format!("{}円(${rounded_usd})", converted.round()),
+        currency => format!("{converted:.2} {currency}(${rounded_usd})"),
+    }
+}
+
+/// 基準からの増減を矢印と差額・割合で表す (例: `▲150円($1) (+15.0%)`)。基準が 0 なら割合は付けない
+pub fn format_change(current: f64, baseline: f64, exchange: &Exchange) -> String {
+    let delta = current - baseline;
+    // 表示の最小単位 (0.01 USD) 未満の差は変化なしとする
+    if delta.abs() < 0.005 {
+        return "→ 変化なし".to_string();
+    }
+    let arrow = if delta > 0.0 { "▲" } else { "▼" };
+    let amount = format_cost(delta.abs(), exchange);
+    if baseline > 0.0 {
+        format!("{arrow}{amount} ({:+.1}%)", delta / baseline * 100.0)
+    } else {
+        format!("{arrow}{amount}")
+    }
+}
+
+/// 金額の幅を整形する (例: `42000〜48000円($280〜$320)`)
+pub fn format_cost_range(lower_usd: f64, upper_usd: f64, exchange: &Exchange) -> String {
+    let round_usd = |cost: f64| Decimal::from_f64(cost).map(|d| d.round_dp(2)).unwrap_or(Decimal::ZERO);
+    let (lower, upper) = (lower_usd * exchange.rate, upper_usd * exchange.rate);
+    let usd = format!("${}〜${}", round_usd(lower_usd), round_usd(upper_usd));
+    match exchange.currency.as_str() {
+        "USD" => usd,
+        "JPY" => format!("{}〜{}円({usd})", lower.round(), upper.round()),
+        currency => format!("{lower:.2}〜{upper:.2} {currency}({usd})"),
+    }
+}
+
+pub fn format_service_costs(services: &[ServiceCost], exchange: &Exchange, display_count: usize, with_shares: bool) -> Result<String, MyError> {
+    Ok(format!("```\n{}\n```", format_ranking_lines(services, exchange, display_count, with_shares)?))
+}
+
+/// 上位 `display_count` 件より後のサービスを `その他 (n サービス)` の 1 行にまとめる。超えていなければ `None`
+pub fn roll_up_others(services: &[ServiceCost], display_count: usize) -> Option<ServiceCost> {
+    let hidden = services.get(display_count..).filter(|hidden| !hidden.is_empty())?;
+    Some(ServiceCost {
+        previous: hidden.iter().map(|service| service.previous).sum(),
+        ..ServiceCost::new(format!("その他 ({}サービス)", hidden.len()), hidden.iter().map(|service| service.amount).sum())
+    })
+}
+
+/// ランキングを 1 サービス 1 行で整形する。表示件数を超えた分は `その他` の 1 行にまとめる。
+/// `with_shares` なら料金の後に全サービスの合計に占める割合を添える
+pub fn format_ranking_lines(services: &[ServiceCost], exchange: &Exchange, display_count: usize, with_shares: bool) -> Result<String, MyError> {
+    let mut formatted_cost_per_service = String::new();
+
+    let total: f64 = services.iter().map(|service| service.amount).sum();
+    let others = roll_up_others(services, display_count);
+    for service in services.iter().take(display_count).chain(&others) {
+        write!(formatted_cost_per_service, "{:<50}:  {}", service.label(), format_cost(service.amount, exchange))?;
+        if with_shares && total > 0.0 {
+            write!(formatted_cost_per_service, "  {:.0}%", service.amount / total * 100.0)?;
+        }
+        if let Some(previous) = service.previous {
+            write!(formatted_cost_per_service, "  {}", format_change(service.amount, previous, exchange))?;
+        }
+        if let Some(secondary) = &service.secondary {
+            write!(formatted_cost_per_service, "  ({})", secondary.format(exchange))?;
+        }
+        if let Some(month_forecast) = service.month_forecast {
+            write!(formatted_cost_per_service, "  (今月の予測: {})", format_cost(month_forecast, exchange))?;
+        }
+        writeln!(formatted_cost_per_service)?;
+    }
+    Ok(formatted_cost_per_service)
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// テスト用の為替レート (1 USD = 150 円)
+    pub fn exchange() -> Exchange {
+        Exchange { currency: "JPY".to_string(), rate: 150.0 }
+    }
+
+    /// テスト用のレポート
+    pub fn report() -> Report {
+        Report {
+            report_type: ReportType::Daily,
+            exchange: exchange(),
+            period: "前々日".to_string(),
+            daily_total: 12.5,
+            estimated: false,
+            moving_average: None,
+            daily_history: Vec::new(),
+            comparisons: Vec::new(),
+            untagged_cost: None,
+            environments: Vec::new(),
+            recent_average: None,
+            monthly_cost: 120.0,
+            last_year_month_to_date: None,
+            ai_spotlight: None,
+            forecast: 310.0,
+            forecast_interval: None,
+            extrapolation: None,
+            quarter_forecast: None,
+            year_forecast: None,
+            last_month_cost: Some(280.0),
+            budget_runway: None,
+            budget_outcome: None,
+            services: vec![
+                ServiceCost::new("Amazon Elastic Compute Cloud - Compute".to_string(), 8.0),
+                ServiceCost::new("Amazon Simple Storage Service".to_string(), 3.0),
+                ServiceCost::new("AWS Lambda".to_string(), 1.5),
+            ],
+            display_count: 5,
+            ranking_medals: false,
+            ranking_floor: 0.0,
+            alerts: Vec::new(),
+            sections: Sections::default(),
+            message_prefix: None,
+            breakdowns: Vec::new(),
+            month_composition: Vec::new(),
+            notice: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_cost() {
+        assert_eq!(format_cost(1.234, &fixtures::exchange()), "185円($1.23)");
+        assert_eq!(format_cost(1.234, &Exchange::usd()), "$1.23");
+        assert_eq!(format_cost(1.234, &Exchange { currency: "EUR".to_string(), rate: 0.9 }), "1.11 EUR($1.23)");
+    }
+
+    #[test]
+    fn test_format_forecast() {
+        let mut report = fixtures::report();
+        assert_eq!(report.format_forecast(), "46500円($310)");
+        report.forecast_interval = Some(ForecastInterval { lower: 280.0, upper: 320.0 });
+        assert_eq!(report.format_forecast(), "42000〜48000円($280〜$320)");
+        assert_eq!(format_cost_range(280.0, 320.0, &Exchange::usd()), "$280〜$320");
+    }
+
+    #[test]
+    fn test_long_range_forecasts() {
+        let mut report = fixtures::report();
+        assert_eq!(report.totals().len(), 4);
+        report.year_forecast = Some(3600.0);
+        assert_eq!(report.totals()[3], ("今年の予測".to_string(), "540000円($3600)".to_string()));
+        assert_eq!(report.totals().last().unwrap(), &("先月の合計".to_string(), "42000円($280)".to_string()));
+        assert!(report.summary_text().unwrap().contains("今年の予測:540000円($3600)"));
+    }
+
+    #[test]
+    fn test_budget_runway() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let october = Window::month_containing(date(1), 1).unwrap();
+        let runway = BudgetRunway::new(300.0, 100.0, date(11), october);
+        assert_eq!(runway.daily_burn, 10.0);
+        assert_eq!(runway.remaining, 200.0);
+        assert_eq!(runway.exhausted_on, Some(date(31)));
+        assert_eq!(BudgetRunway::new(300.0, 100.0, date(21), october).exhausted_on, None);
+        assert_eq!(BudgetRunway::new(300.0, 350.0, date(21), october).format_exhaustion(), "超過済み");
+        // 21 日始まりの月なら 10/21 は月の初日で、11/20 までに使い切る
+        let fiscal_month = Window::month_containing(date(21), 21).unwrap();
+        assert_eq!(BudgetRunway::new(300.0, 100.0, date(22), fiscal_month).exhausted_on, Some(date(24)));
+
+        let report = Report { budget_runway: Some(runway), ..fixtures::report() };
+        assert!(report.summary_text().unwrap().contains("予算到達見込み:2026-10-31"));
+
+        let report = Report { report_type: ReportType::Monthly, budget_outcome: Some(10.0), ..report };
+        assert_eq!(report.budget_lines(), [("予算との差".to_string(), "▲375円($2.5) (+25.0%) (予算 1500円($10))".to_string())]);
+        assert!(report.last_month_line().is_none());
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        let october = Window::month_containing(date(1), 1).unwrap();
+        assert_eq!(extrapolate(100.0, date(11), october), 310.0);
+        assert_eq!(extrapolate(5.0, date(1), october), 155.0);
+        let fiscal_month = Window::month_containing(date(11), 21).unwrap();
+        assert_eq!(extrapolate(100.0, date(11), fiscal_month), 150.0);
+    }
+
+    #[test]
+    fn test_format_daily_total() {
+        let report = Report { moving_average: Some(10.0), ..fixtures::report() };
+        assert_eq!(report.format_daily_total(), "1875円($12.5) (7日移動平均 1500円($10))");
+        assert_eq!(fixtures::report().format_daily_total(), "1875円($12.5)");
+    }
+
+    #[test]
+    fn test_numbered_ranking() {
+        let report = Report { display_count: 2, ..fixtures::report() };
+        let names: Vec<_> = report.numbered_ranking().into_iter().map(|service| service.name).collect();
+        assert_eq!(names, ["1. Amazon Elastic Compute Cloud - Compute", "2. Amazon Simple Storage Service", "その他 (1サービス)"]);
+        let report = Report { ranking_medals: true, ..report };
+        assert_eq!(report.ranked_name(1, "Amazon S3"), "🥈 Amazon S3");
+        assert_eq!(report.ranked_name(3, "AWS Lambda"), "4. AWS Lambda");
+    }
+
+    #[test]
+    fn test_ranking_floor() {
+        let report = Report { ranking_floor: 2.0, ..fixtures::report() };
+        assert_eq!(report.ranking().count(), 2);
+        let others = report.others().unwrap();
+        assert_eq!((others.name.as_str(), others.amount), ("その他 (1サービス)", 1.5));
+    }
+
+    #[test]
+    fn test_format_change() {
+        let exchange = fixtures::exchange();
+        assert_eq!(format_change(11.5, 10.0, &exchange), "▲225円($1.5) (+15.0%)");
+        assert_eq!(format_change(8.0, 10.0, &exchange), "▼300円($2) (-20.0%)");
+        assert_eq!(format_change(3.0, 0.0, &exchange), "▲450円($3)");
+        assert_eq!(format_change(10.0, 10.001, &exchange), "→ 変化なし");
+
+        let report = Report { comparisons: vec![Comparison { label: "前日比".to_string(), baseline: 10.0 }], ..fixtures::report() };
+        assert!(report.summary_text().unwrap().contains("前々日料金:1875円($12.5)\n　前日比: ▲375円($2.5) (+25.0%)\n"));
+
+        let report = Report { last_year_month_to_date: Some(150.0), ..fixtures::report() };
+        assert_eq!(report.totals()[2], ("前年同期比".to_string(), "▼4500円($30) (-20.0%)".to_string()));
+    }
+
+    #[test]
+    fn test_ai_spotlight_line() {
+        let report = Report { ai_spotlight: Some(Spotlight { amount: 40.0, previous: 20.0 }), ..fixtures::report() };
+        assert_eq!(report.totals()[2], ("AI サービス".to_string(), "6000円($40) (前月同期比 ▲3000円($20) (+100.0%))".to_string()));
+        assert!(report.summary_text().unwrap().contains("現時点料金:18000円($120)\n　AI サービス: 6000円($40)"));
+    }
+
+    #[test]
+    fn test_untagged_line() {
+        let report = Report { untagged_cost: Some(2.25), ..fixtures::report() };
+        assert_eq!(report.totals()[1], ("未タグ付け".to_string(), "338円($2.25) / 18%".to_string()));
+        assert!(report.summary_text().unwrap().contains("前々日料金:1875円($12.5)\n　未タグ付け: 338円($2.25) / 18%\n"));
+    }
+
+    #[test]
+    fn test_environment_line() {
+        let environment = |name: &str, amount| ServiceCost::new(name.to_string(), amount);
+        let report = Report { environments: vec![environment("prod", 10.0), environment("dev", 2.5)], ..fixtures::report() };
+        assert_eq!(report.totals()[1], ("環境別".to_string(), "prod 1500円($10) 80% / dev 375円($2.5) 20%".to_string()));
+        assert!(report.summary_text().unwrap().contains("\n　環境別: prod 1500円($10) 80% / dev 375円($2.5) 20%\n"));
+    }
+
+    #[test]
+    fn test_label() {
+        let service = fixtures::report().services[2].clone();
+        assert_eq!(service.label(), "AWS Lambda");
+        let annotations = Annotations {
+            over_budget: Some(15.0),
+            rank_change: Some("↑2".to_string()),
+            usage_type_shares: vec![("Request".to_string(), 60.0), ("Lambda-GB-Second".to_string(), 40.0)],
+        };
+        let service = ServiceCost { annotations, ..service };
+        let label = "AWS Lambda ⚠ 予算超過 +15% ↑2 (内訳: Request 60% / Lambda-GB-Second 40%)";
+        assert_eq!(service.label(), label);
+        let formatted = format_ranking_lines(&[service], &fixtures::exchange(), 1, false).unwrap();
+        assert!(formatted.starts_with(&format!("{label:<50}:")));
+    }
+
+    #[test]
+    fn test_format_service_costs() {
+        let services = vec![
+            ServiceCost::new("Amazon EC2".to_string(), 2.0),
+            ServiceCost::new("Amazon S3".to_string(), 1.0),
+        ];
+        let formatted = format_service_costs(&services, &fixtures::exchange(), 1, true).unwrap();
+        assert!(formatted.contains("Amazon EC2"));
+        assert!(!formatted.contains("Amazon S3"));
+        assert!(formatted.contains(&format!("{:<50}:  300円($2)  67%", "Amazon EC2")));
+        assert!(formatted.contains(&format!("{:<50}:  150円($1)  33%", "その他 (1サービス)")));
+        let formatted = format_service_costs(&services, &fixtures::exchange(), 1, false).unwrap();
+        assert!(formatted.contains(&format!("{:<50}:  300円($2)\n", "Amazon EC2")));
+
+        let services = vec![ServiceCost { secondary: Some(Secondary::Usage { quantity: 48.0 }), ..ServiceCost::new("Amazon EC2".to_string(), 2.0) }];
+        let formatted = format_ranking_lines(&services, &fixtures::exchange(), 1, false).unwrap();
+        assert!(formatted.ends_with(":  300円($2)  (使用量: 48.00)\n"));
+
+        let services = vec![ServiceCost { month_forecast: Some(60.0), ..ServiceCost::new("Amazon EC2".to_string(), 2.0) }];
+        let formatted = format_ranking_lines(&services, &fixtures::exchange(), 1, false).unwrap();
+        assert!(formatted.ends_with(":  300円($2)  (今月の予測: 9000円($60))\n"));
+    }
+
+    #[test]
+    fn test_daily_label() {
+        let mut report = fixtures::report();
+        assert_eq!(report.daily_label(), "前々日料金");
+        report.estimated = true;
+        assert!(report.summary_text().unwrap().contains("前々日料金(※速報値):"));
+    }
+
+    #[test]
+    fn test_composition_text() {
+        let mut report = fixtures::report();
+        assert_eq!(report.composition_text(), None);
+
+        report.month_composition = vec![
+            ServiceCost::new("利用".to_string(), 130.0),
+            ServiceCost::new("クレジット".to_string(), -10.0),
+        ];
+        assert_eq!(report.composition_text().unwrap(), "利用 19500円($130) / クレジット -1500円($-10)");
+        assert!(report.summary_text().unwrap().contains("　内訳: 利用"));
+    }
+
+    #[test]
+    fn test_breakdowns_text() {
+        let mut report = fixtures::report();
+        assert_eq!(report.breakdowns_text().unwrap(), "");
+
+        report.breakdowns.push(Breakdown {
+            title: "前々日のリージョン別料金".to_string(),
+            items: vec![ServiceCost::new("us-east-1".to_string(), 10.0)],
+        });
+        let text = report.to_text().unwrap();
+        assert!(text.contains("■前々日のリージョン別料金\n```\nus-east-1"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use aws_sdk_secretsmanager as secretsmanager;
+use serde::Deserialize;
+
+use crate::MyError;
+use crate::config::env_var;
+
+/// 取得済みのシークレット。ウォームスタートの呼び出しでは Secrets Manager を呼ばない
+static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// 値を直接指定するか、Secrets Manager のシークレットで指定する機密情報 (Webhook URL や API キー)
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Secret {
+    Plain(String),
+    /// 設定ファイルでは `{ "secret_id": "..." }` と書く
+    SecretsManager { secret_id: String },
+}
+
+impl Secret {
+    /// 環境変数 `{prefix}{name}`、なければ `{prefix}{name}_SECRET_ID` から読み込む。どちらも未設定なら `None`
+    pub fn from_env(prefix: &str, name: &str) -> Option<Self> {
+        env_var(prefix, name).map(Self::Plain)
+            .or_else(|| env_var(prefix, &format!("{name}_SECRET_ID")).map(|secret_id| Self::SecretsManager { secret_id }))
+    }
+
+    /// 値を返す。Secrets Manager のシークレットは初回だけ取得する
+    pub async fn resolve(&self) -> Result<String, MyError> {
+        match self {
+            Self::Plain(value) => Ok(value.clone()),
+            Self::SecretsManager { secret_id } => get_secret_string(secret_id).await,
+        }
+    }
+}
+
+/// Secrets Manager からシークレット文字列を取得する。同じシークレットは一度だけ取得する
+pub async fn get_secret_string(secret_id: &str) -> Result<String, MyError> {
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(value) = cache.lock().unwrap_or_else(PoisonError::into_inner).get(secret_id) {
+        return Ok(value.clone());
+    }
+
+    let config = aws_config::load_from_env().await;
+    let client = secretsmanager::Client::new(&config);
+    let result = client.get_secret_value().secret_id(secret_id).send().await?;
+    let value = result.secret_string.ok_or_else(|| format!("シークレット {secret_id} に文字列が設定されていません"))?;
+    cache.lock().unwrap_or_else(PoisonError::into_inner).insert(secret_id.to_string(), value.clone());
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_secret() {
+        let plain: Secret = serde_json::from_value(json!("https://example.com/hook")).unwrap();
+        assert!(matches!(plain, Secret::Plain(value) if value == "https://example.com/hook"));
+        let secret: Secret = serde_json::from_value(json!({ "secret_id": "billing/slack" })).unwrap();
+        assert!(matches!(secret, Secret::SecretsManager { secret_id } if secret_id == "billing/slack"));
+    }
+}
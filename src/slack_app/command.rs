@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use lambda_runtime::LambdaEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{build_report, MyError};
+use crate::config::{feature_flags, Config};
+use super::{invoke_self, raw_body, respond, response, signing_secret, verify_signature};
+
+/// `/awscost` スラッシュコマンドのハンドラーが受け取るイベント
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum CommandEvent {
+    /// 応答後に自分自身を非同期で呼び出したときのペイロード
+    Deferred(DeferredCommand),
+    /// API Gateway (HTTP API) / Function URL 経由の Slack からのリクエスト
+    Http(Box<ApiGatewayV2httpRequest>),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeferredCommand {
+    response_url: String,
+}
+
+/// スラッシュコマンドを受け付ける。署名を検証したらすぐに応答し、レポートは `response_url` に返す
+pub async fn handler(config: &Config, event: LambdaEvent<CommandEvent>) -> Result<ApiGatewayV2httpResponse, lambda_runtime::Error> {
+    match event.payload {
+        CommandEvent::Deferred(command) => {
+            let config = &feature_flags::apply(config).await;
+            let message = match build_report(config).await.and_then(|report| report.to_text()) {
+                Ok(text) => json!({ "response_type": "in_channel", "text": text }),
+                Err(e) => json!({ "response_type": "ephemeral", "text": format!("レポートの作成に失敗しました: {e}") }),
+            };
+            respond(&command.response_url, &message).await?;
+            Ok(response(200, None))
+        }
+        CommandEvent::Http(request) => Ok(accept(config, &request).await),
+    }
+}
+
+async fn accept(config: &Config, request: &ApiGatewayV2httpRequest) -> ApiGatewayV2httpResponse {
+    let response_url = match verify(config, request) {
+        Ok(response_url) => response_url,
+        Err(e) => {
+            eprintln!("スラッシュコマンドを拒否しました: {e}");
+            return response(401, None);
+        }
+    };
+    if let Err(e) = invoke_self(&DeferredCommand { response_url }).await {
+        eprintln!("レポート作成の呼び出しに失敗しました: {e}");
+        return response(200, Some(&json!({ "response_type": "ephemeral", "text": "レポートの作成を開始できませんでした" })));
+    }
+    response(200, Some(&json!({ "response_type": "ephemeral", "text": "料金を集計しています…" })))
+}
+
+/// 署名を検証し、フォームの `response_url` を返す
+fn verify(config: &Config, request: &ApiGatewayV2httpRequest) -> Result<String, MyError> {
+    let body = raw_body(request)?;
+    verify_signature(signing_secret(config)?, &request.headers, &body, chrono::Utc::now().timestamp())?;
+    let form: HashMap<String, String> = serde_urlencoded::from_bytes(&body)?;
+    form.get("response_url").cloned().ok_or_else(|| "response_url がありません".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_deferred() {
+        let event: CommandEvent = serde_json::from_value(json!({ "response_url": "https://hooks.slack.com/commands/1" })).unwrap();
+        assert!(matches!(event, CommandEvent::Deferred(_)));
+    }
+}
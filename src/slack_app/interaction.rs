@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use lambda_runtime::LambdaEvent;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{build_report, MyError};
+use crate::config::Config;
+use crate::notifier::slack::blocks::SHOW_DETAILS_ACTION_ID;
+use super::{invoke_self, raw_body, respond, response, signing_secret, verify_signature};
+
+/// ボタン操作 (Interactivity) のハンドラーが受け取るイベント
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum InteractionEvent {
+    /// 応答後に自分自身を非同期で呼び出したときのペイロード
+    Deferred(DeferredAction),
+    /// API Gateway (HTTP API) / Function URL 経由の Slack からのリクエスト
+    Http(Box<ApiGatewayV2httpRequest>),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeferredAction {
+    action_id: String,
+    response_url: String,
+}
+
+/// ボタン操作を受け付ける。署名を検証したらすぐに応答し、結果は `response_url` に返す
+pub async fn handler(config: &Config, event: LambdaEvent<InteractionEvent>) -> Result<ApiGatewayV2httpResponse, lambda_runtime::Error> {
+    match event.payload {
+        InteractionEvent::Deferred(action) => {
+            let message = match action.action_id.as_str() {
+                SHOW_DETAILS_ACTION_ID => match build_report(config).await.and_then(|report| report.full_breakdown_text()) {
+                    Ok(text) => json!({ "response_type": "ephemeral", "replace_original": false, "text": text }),
+                    Err(e) => json!({ "response_type": "ephemeral", "replace_original": false, "text": format!("内訳の作成に失敗しました: {e}") }),
+                },
+                other => {
+                    eprintln!("未知の action_id です: {other}");
+                    return Ok(response(200, None));
+                }
+            };
+            respond(&action.response_url, &message).await?;
+            Ok(response(200, None))
+        }
+        InteractionEvent::Http(request) => Ok(accept(config, &request).await),
+    }
+}
+
+async fn accept(config: &Config, request: &ApiGatewayV2httpRequest) -> ApiGatewayV2httpResponse {
+    let actions = match verify(config, request) {
+        Ok(actions) => actions,
+        Err(e) => {
+            eprintln!("ボタン操作を拒否しました: {e}");
+            return response(401, None);
+        }
+    };
+    for action in actions {
+        if let Err(e) = invoke_self(&action).await {
+            eprintln!("{} の処理の呼び出しに失敗しました: {e}", action.action_id);
+        }
+    }
+    response(200, None)
+}
+
+/// 署名を検証し、`payload` に含まれるボタン操作を返す
+fn verify(config: &Config, request: &ApiGatewayV2httpRequest) -> Result<Vec<DeferredAction>, MyError> {
+    let body = raw_body(request)?;
+    verify_signature(signing_secret(config)?, &request.headers, &body, chrono::Utc::now().timestamp())?;
+    let form: HashMap<String, String> = serde_urlencoded::from_bytes(&body)?;
+    let payload: Value = serde_json::from_str(form.get("payload").ok_or("payload がありません")?)?;
+    parse_actions(&payload)
+}
+
+fn parse_actions(payload: &Value) -> Result<Vec<DeferredAction>, MyError> {
+    let response_url = payload["response_url"].as_str().ok_or("response_url がありません")?;
+    Ok(payload["actions"].as_array().into_iter().flatten()
+        .filter_map(|action| action["action_id"].as_str())
+        .map(|action_id| DeferredAction { action_id: action_id.to_string(), response_url: response_url.to_string() })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions() {
+        let payload = json!({
+            "type": "block_actions",
+            "response_url": "https://hooks.slack.com/actions/1",
+            "actions": [{ "action_id": SHOW_DETAILS_ACTION_ID, "value": SHOW_DETAILS_ACTION_ID }],
+        });
+        let actions = parse_actions(&payload).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action_id, SHOW_DETAILS_ACTION_ID);
+    }
+
+    #[test]
+    fn test_deserialize_deferred() {
+        let event: InteractionEvent = serde_json::from_value(json!({
+            "action_id": SHOW_DETAILS_ACTION_ID,
+            "response_url": "https://hooks.slack.com/actions/1",
+        })).unwrap();
+        assert!(matches!(event, InteractionEvent::Deferred(_)));
+    }
+}
@@ -0,0 +1,118 @@
+pub mod command;
+pub mod interaction;
+
+use aws_lambda_events::apigw::{ApiGatewayV2httpRequest, ApiGatewayV2httpResponse};
+use aws_lambda_events::encodings::Body;
+use aws_lambda_events::http::HeaderMap;
+use aws_sdk_lambda as lambda;
+use aws_sdk_lambda::primitives::Blob;
+use aws_sdk_lambda::types::InvocationType;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::MyError;
+use crate::config::Config;
+use crate::notifier::post_json_with_retry;
+
+/// リプレイ攻撃対策として受け付けるリクエストの時刻のずれ (秒)
+const MAX_TIMESTAMP_SKEW_SECS: i64 = 60 * 5;
+
+/// 設定の署名シークレット (`SLACK_SIGNING_SECRET`) を返す
+fn signing_secret(config: &Config) -> Result<&str, MyError> {
+    config.slack_signing_secret.as_deref().ok_or_else(|| "SLACK_SIGNING_SECRET が未設定です".into())
+}
+
+/// API Gateway / Function URL のボディを復号して返す
+fn raw_body(request: &ApiGatewayV2httpRequest) -> Result<Vec<u8>, MyError> {
+    let body = request.body.clone().unwrap_or_default();
+    if request.is_base64_encoded {
+        Ok(STANDARD.decode(body)?)
+    } else {
+        Ok(body.into_bytes())
+    }
+}
+
+/// Slack の署名 (`X-Slack-Signature`) を検証する
+/// https://api.slack.com/authentication/verifying-requests-from-slack
+fn verify_signature(secret: &str, headers: &HeaderMap, body: &[u8], now: i64) -> Result<(), MyError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).ok_or_else(|| format!("{name} ヘッダーがありません"));
+    let timestamp = header("x-slack-request-timestamp")?;
+    let signature = header("x-slack-signature")?;
+
+    let timestamp_secs: i64 = timestamp.parse().map_err(|_| format!("タイムスタンプが不正です: {timestamp}"))?;
+    if (now - timestamp_secs).abs() > MAX_TIMESTAMP_SKEW_SECS {
+        return Err("タイムスタンプが古すぎます".into());
+    }
+
+    let expected = hex::decode(signature.strip_prefix("v0=").ok_or("署名の形式が不正です")?)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| format!("HMAC の鍵が不正です: {e}"))?;
+    mac.update(format!("v0:{timestamp}:").as_bytes());
+    mac.update(body);
+    mac.verify_slice(&expected).map_err(|_| "署名が一致しません".into())
+}
+
+fn response(status_code: i64, body: Option<&Value>) -> ApiGatewayV2httpResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", "application/json".parse().unwrap());
+    ApiGatewayV2httpResponse {
+        status_code,
+        headers,
+        body: body.map(|body| Body::Text(body.to_string())),
+        ..Default::default()
+    }
+}
+
+/// 自分自身を非同期 (Event) で呼び出す。
+/// Slack は 3 秒以内の応答を求めるため、時間のかかる集計は別の呼び出しで行い `response_url` に返す
+async fn invoke_self<T: Serialize>(payload: &T) -> Result<(), MyError> {
+    let function_name = std::env::var("AWS_LAMBDA_FUNCTION_NAME")?;
+    let config = aws_config::load_from_env().await;
+    lambda::Client::new(&config).invoke()
+        .function_name(function_name)
+        .invocation_type(InvocationType::Event)
+        .payload(Blob::new(serde_json::to_vec(payload)?))
+        .send()
+        .await?;
+    Ok(())
+}
+
+/// `response_url` にメッセージを返す
+async fn respond(response_url: &str, message: &Value) -> Result<(), MyError> {
+    post_json_with_retry(&Client::new(), response_url, message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("v0:{timestamp}:{body}").as_bytes());
+        format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn headers(timestamp: i64, signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-slack-request-timestamp", timestamp.to_string().parse().unwrap());
+        headers.insert("x-slack-signature", signature.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = "command=%2Fawscost&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2F1";
+        let now = 1_700_000_000;
+        let signature = sign("secret", now, body);
+
+        assert!(verify_signature("secret", &headers(now, &signature), body.as_bytes(), now).is_ok());
+        assert!(verify_signature("other", &headers(now, &signature), body.as_bytes(), now).is_err());
+        assert!(verify_signature("secret", &headers(now, &signature), b"tampered", now).is_err());
+        assert!(verify_signature("secret", &headers(now, &signature), body.as_bytes(), now + 600).is_err());
+    }
+}